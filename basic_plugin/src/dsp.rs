@@ -0,0 +1,30 @@
+//! The signal processing stages used by [`Basic`][crate::Basic]. The stages don't read parameters
+//! themselves. Most of them are fed one sample at a time, the oversampler works on whole blocks.
+
+pub mod auto_gain;
+pub mod biquad;
+pub mod compressor;
+pub mod crossover;
+pub mod ducker;
+pub mod enhancer;
+pub mod equalizer;
+pub mod fft;
+pub mod gate;
+pub mod k_weighting;
+pub mod limiter;
+pub mod oversampling;
+pub mod saturation;
+pub mod stereo;
+pub mod subharmonic;
+pub mod transient;
+pub mod true_peak;
+
+/// The one-pole smoothing weight that makes an envelope follower reach about 63% of a step after
+/// `time_ms` milliseconds.
+pub fn envelope_weight(sample_rate: f32, time_ms: f32) -> f32 {
+    if time_ms <= 0.0 {
+        0.0
+    } else {
+        (-1.0 / (sample_rate * time_ms / 1000.0)).exp()
+    }
+}
@@ -0,0 +1,128 @@
+use nih_plug::prelude::*;
+
+use super::envelope_weight;
+use super::k_weighting::KWeighting;
+
+/// The loudness is compared over about the same time as the short-term loudness, which is long
+/// enough that the compensation doesn't follow individual notes.
+const INTEGRATION_MS: f32 = 3000.0;
+/// How quickly the compensation fades in and out when it's switched on or off.
+const FADE_MS: f32 = 50.0;
+/// The compensation never goes beyond this, so a stage that mutes the signal can't make it run
+/// away.
+const MAX_GAIN_DB: f32 = 24.0;
+/// Below this K-weighted mean square, which is about -70 LUFS, a signal counts as silent and the
+/// compensation is held where it was.
+const SILENCE_MEAN_SQUARE: f32 = 1e-7;
+
+/// Matches the loudness of the processed signal to the unprocessed input, so A/B comparisons
+/// aren't fooled by the processed signal simply being louder. Both signals are K-weighted and
+/// averaged over a few seconds, and the gain that makes up the difference is applied to the
+/// processed signal.
+#[derive(Default)]
+pub struct AutoGain {
+    weight: f32,
+    fade_weight: f32,
+
+    input_filters: Vec<KWeighting>,
+    output_filters: Vec<KWeighting>,
+    input_mean_square: f32,
+    output_mean_square: f32,
+
+    /// The gain that matches the output to the input.
+    target_gain: f32,
+    /// The applied gain, which fades towards the target gain, or to unity when switched off.
+    gain: f32,
+}
+
+impl AutoGain {
+    /// Allocate state for `num_channels` channels. This must be called from `initialize()`.
+    pub fn initialize(&mut self, num_channels: usize, sample_rate: f32) {
+        self.weight = envelope_weight(sample_rate, INTEGRATION_MS);
+        self.fade_weight = envelope_weight(sample_rate, FADE_MS);
+
+        self.input_filters
+            .resize_with(num_channels, KWeighting::default);
+        self.output_filters
+            .resize_with(num_channels, KWeighting::default);
+        for filter in self
+            .input_filters
+            .iter_mut()
+            .chain(&mut self.output_filters)
+        {
+            filter.set_sample_rate(sample_rate);
+        }
+
+        self.reset();
+    }
+
+    pub fn reset(&mut self) {
+        for filter in self
+            .input_filters
+            .iter_mut()
+            .chain(&mut self.output_filters)
+        {
+            filter.reset();
+        }
+        self.input_mean_square = 0.0;
+        self.output_mean_square = 0.0;
+
+        self.target_gain = 1.0;
+        self.gain = 1.0;
+    }
+
+    /// Measure a frame of the unprocessed input. This should happen even while the compensation is
+    /// switched off, so it's right as soon as it gets switched on.
+    pub fn measure_input(&mut self, frame: &[f32]) {
+        self.input_mean_square = average(
+            &mut self.input_filters,
+            frame,
+            self.input_mean_square,
+            self.weight,
+        );
+    }
+
+    /// Measure a frame of the processed signal, before the compensation is applied, and return the
+    /// gain to apply to it. The gain fades to 1.0 when `enabled` is false.
+    pub fn next_gain(&mut self, frame: &[f32], enabled: bool) -> f32 {
+        self.output_mean_square = average(
+            &mut self.output_filters,
+            frame,
+            self.output_mean_square,
+            self.weight,
+        );
+
+        if self.input_mean_square > SILENCE_MEAN_SQUARE
+            && self.output_mean_square > SILENCE_MEAN_SQUARE
+        {
+            let max_gain = util::db_to_gain(MAX_GAIN_DB);
+            self.target_gain = (self.input_mean_square / self.output_mean_square)
+                .sqrt()
+                .clamp(max_gain.recip(), max_gain);
+        }
+
+        let target_gain = if enabled { self.target_gain } else { 1.0 };
+        self.gain = self.gain * self.fade_weight + target_gain * (1.0 - self.fade_weight);
+
+        self.gain
+    }
+
+    /// The gain applied to the last frame.
+    pub fn gain(&self) -> f32 {
+        self.gain
+    }
+}
+
+/// Add a frame's K-weighted power, summed over all channels, to a running average.
+fn average(filters: &mut [KWeighting], frame: &[f32], mean_square: f32, weight: f32) -> f32 {
+    let power: f32 = filters
+        .iter_mut()
+        .zip(frame)
+        .map(|(filter, sample)| {
+            let weighted = filter.process(*sample);
+            weighted * weighted
+        })
+        .sum();
+
+    mean_square * weight + power * (1.0 - weight)
+}
@@ -0,0 +1,166 @@
+use std::f32::consts::{FRAC_1_SQRT_2, PI};
+
+/// The Q value for a second order Butterworth filter.
+pub const BUTTERWORTH_Q: f32 = FRAC_1_SQRT_2;
+
+/// A second order IIR filter in transposed direct form II.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Biquad {
+    pub coefficients: BiquadCoefficients,
+    s1: f32,
+    s2: f32,
+}
+
+/// Normalized coefficients for a [`Biquad`], with `a0` divided out. The formulas come from the
+/// RBJ Audio EQ Cookbook.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BiquadCoefficients {
+    b0: f32,
+    b1: f32,
+    b2: f32,
+    a1: f32,
+    a2: f32,
+}
+
+impl Default for BiquadCoefficients {
+    fn default() -> Self {
+        Self::identity()
+    }
+}
+
+impl Biquad {
+    pub fn process(&mut self, sample: f32) -> f32 {
+        let result = self.coefficients.b0 * sample + self.s1;
+
+        self.s1 = self.coefficients.b1 * sample - self.coefficients.a1 * result + self.s2;
+        self.s2 = self.coefficients.b2 * sample - self.coefficients.a2 * result;
+
+        result
+    }
+
+    /// Clear the filter's state without touching its coefficients.
+    pub fn reset(&mut self) {
+        self.s1 = 0.0;
+        self.s2 = 0.0;
+    }
+}
+
+impl BiquadCoefficients {
+    /// Coefficients that pass the signal through unchanged.
+    pub const fn identity() -> Self {
+        Self {
+            b0: 1.0,
+            b1: 0.0,
+            b2: 0.0,
+            a1: 0.0,
+            a2: 0.0,
+        }
+    }
+
+    pub fn lowpass(sample_rate: f32, frequency: f32, q: f32) -> Self {
+        let (cos_omega, alpha) = omega_terms(sample_rate, frequency, q);
+
+        let b1 = 1.0 - cos_omega;
+        let b0 = b1 / 2.0;
+        Self::from_raw(b0, b1, b0, 1.0 + alpha, -2.0 * cos_omega, 1.0 - alpha)
+    }
+
+    pub fn highpass(sample_rate: f32, frequency: f32, q: f32) -> Self {
+        let (cos_omega, alpha) = omega_terms(sample_rate, frequency, q);
+
+        let b1 = -(1.0 + cos_omega);
+        let b0 = -b1 / 2.0;
+        Self::from_raw(b0, b1, b0, 1.0 + alpha, -2.0 * cos_omega, 1.0 - alpha)
+    }
+
+    /// A band-pass filter with a constant 0 dB peak gain.
+    pub fn bandpass(sample_rate: f32, frequency: f32, q: f32) -> Self {
+        let (cos_omega, alpha) = omega_terms(sample_rate, frequency, q);
+
+        Self::from_raw(
+            alpha,
+            0.0,
+            -alpha,
+            1.0 + alpha,
+            -2.0 * cos_omega,
+            1.0 - alpha,
+        )
+    }
+
+    /// A peaking filter that boosts or cuts `gain_db` around `frequency`.
+    pub fn peaking(sample_rate: f32, frequency: f32, q: f32, gain_db: f32) -> Self {
+        let (cos_omega, alpha) = omega_terms(sample_rate, frequency, q);
+        let a = shelf_amplitude(gain_db);
+
+        Self::from_raw(
+            1.0 + alpha * a,
+            -2.0 * cos_omega,
+            1.0 - alpha * a,
+            1.0 + alpha / a,
+            -2.0 * cos_omega,
+            1.0 - alpha / a,
+        )
+    }
+
+    /// A shelving filter that boosts or cuts `gain_db` below `frequency`. `q` sets the steepness
+    /// of the transition, with [`BUTTERWORTH_Q`] giving the steepest slope without a bump.
+    pub fn low_shelf(sample_rate: f32, frequency: f32, q: f32, gain_db: f32) -> Self {
+        let (cos_omega, alpha) = omega_terms(sample_rate, frequency, q);
+        let a = shelf_amplitude(gain_db);
+        let sqrt_a_alpha = 2.0 * a.sqrt() * alpha;
+
+        Self::from_raw(
+            a * ((a + 1.0) - (a - 1.0) * cos_omega + sqrt_a_alpha),
+            2.0 * a * ((a - 1.0) - (a + 1.0) * cos_omega),
+            a * ((a + 1.0) - (a - 1.0) * cos_omega - sqrt_a_alpha),
+            (a + 1.0) + (a - 1.0) * cos_omega + sqrt_a_alpha,
+            -2.0 * ((a - 1.0) + (a + 1.0) * cos_omega),
+            (a + 1.0) + (a - 1.0) * cos_omega - sqrt_a_alpha,
+        )
+    }
+
+    /// A shelving filter that boosts or cuts `gain_db` above `frequency`.
+    pub fn high_shelf(sample_rate: f32, frequency: f32, q: f32, gain_db: f32) -> Self {
+        let (cos_omega, alpha) = omega_terms(sample_rate, frequency, q);
+        let a = shelf_amplitude(gain_db);
+        let sqrt_a_alpha = 2.0 * a.sqrt() * alpha;
+
+        Self::from_raw(
+            a * ((a + 1.0) + (a - 1.0) * cos_omega + sqrt_a_alpha),
+            -2.0 * a * ((a - 1.0) + (a + 1.0) * cos_omega),
+            a * ((a + 1.0) + (a - 1.0) * cos_omega - sqrt_a_alpha),
+            (a + 1.0) - (a - 1.0) * cos_omega + sqrt_a_alpha,
+            2.0 * ((a - 1.0) - (a + 1.0) * cos_omega),
+            (a + 1.0) - (a - 1.0) * cos_omega - sqrt_a_alpha,
+        )
+    }
+
+    /// Coefficients from a filter design that isn't covered by the cookbook formulas. These are
+    /// normalized by `a0`.
+    pub fn from_raw(b0: f32, b1: f32, b2: f32, a0: f32, a1: f32, a2: f32) -> Self {
+        let a0_recip = a0.recip();
+
+        Self {
+            b0: b0 * a0_recip,
+            b1: b1 * a0_recip,
+            b2: b2 * a0_recip,
+            a1: a1 * a0_recip,
+            a2: a2 * a0_recip,
+        }
+    }
+}
+
+/// Compute `cos(ω)` and `α` for the cookbook formulas. The frequency is kept below Nyquist so
+/// extreme parameter values at low sample rates can't make the filter blow up.
+fn omega_terms(sample_rate: f32, frequency: f32, q: f32) -> (f32, f32) {
+    let frequency = frequency.clamp(1.0, sample_rate * 0.49);
+    let omega = 2.0 * PI * frequency / sample_rate;
+    let (sin_omega, cos_omega) = omega.sin_cos();
+
+    (cos_omega, sin_omega / (2.0 * q))
+}
+
+/// The cookbook's `A` term for the peaking and shelving filters.
+fn shelf_amplitude(gain_db: f32) -> f32 {
+    10.0f32.powf(gain_db / 40.0)
+}
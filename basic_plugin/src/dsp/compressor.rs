@@ -0,0 +1,189 @@
+use nih_plug::prelude::*;
+
+use super::envelope_weight;
+
+#[derive(Params)]
+pub struct CompressorParams {
+    #[id = "comp_threshold"]
+    pub threshold: FloatParam,
+    /// The compressor leaves the signal alone at the default 1:1 ratio.
+    #[id = "comp_ratio"]
+    pub ratio: FloatParam,
+    /// The width of the soft knee around the threshold, in decibels.
+    #[id = "comp_knee"]
+    pub knee: FloatParam,
+    #[id = "comp_attack"]
+    pub attack: FloatParam,
+    #[id = "comp_release"]
+    pub release: FloatParam,
+    #[id = "comp_makeup"]
+    pub makeup: FloatParam,
+}
+
+impl Default for CompressorParams {
+    fn default() -> Self {
+        Self {
+            threshold: FloatParam::new(
+                "Threshold",
+                -18.0,
+                FloatRange::Linear {
+                    min: -60.0,
+                    max: 0.0,
+                },
+            )
+            .with_smoother(SmoothingStyle::Linear(20.0))
+            .with_unit(" dB")
+            .with_step_size(0.1),
+            ratio: FloatParam::new(
+                "Ratio",
+                1.0,
+                FloatRange::Skewed {
+                    min: 1.0,
+                    max: 20.0,
+                    factor: FloatRange::skew_factor(-2.0),
+                },
+            )
+            .with_smoother(SmoothingStyle::Linear(20.0))
+            .with_value_to_string(formatters::v2s_f32_rounded(1))
+            .with_unit(":1"),
+            knee: FloatParam::new(
+                "Knee",
+                6.0,
+                FloatRange::Linear {
+                    min: 0.0,
+                    max: 24.0,
+                },
+            )
+            .with_smoother(SmoothingStyle::Linear(20.0))
+            .with_unit(" dB")
+            .with_step_size(0.1),
+            attack: FloatParam::new(
+                "Comp Attack",
+                10.0,
+                FloatRange::Skewed {
+                    min: 0.1,
+                    max: 100.0,
+                    factor: FloatRange::skew_factor(-2.0),
+                },
+            )
+            .with_unit(" ms")
+            .with_value_to_string(formatters::v2s_f32_rounded(1)),
+            release: FloatParam::new(
+                "Comp Release",
+                120.0,
+                FloatRange::Skewed {
+                    min: 10.0,
+                    max: 1000.0,
+                    factor: FloatRange::skew_factor(-2.0),
+                },
+            )
+            .with_unit(" ms")
+            .with_value_to_string(formatters::v2s_f32_rounded(0)),
+            makeup: FloatParam::new(
+                "Makeup",
+                0.0,
+                FloatRange::Linear {
+                    min: 0.0,
+                    max: 24.0,
+                },
+            )
+            .with_smoother(SmoothingStyle::Linear(20.0))
+            .with_unit(" dB")
+            .with_step_size(0.1),
+        }
+    }
+}
+
+/// The static curve of the compressor for a single sample. These are read from the smoothed
+/// parameter values.
+#[derive(Debug, Clone, Copy)]
+pub struct CompressorCurve {
+    pub threshold_db: f32,
+    pub ratio: f32,
+    pub knee_db: f32,
+    pub makeup_db: f32,
+}
+
+/// A feed-forward compressor with a soft knee. The gain computer works in the decibel domain and
+/// its output is smoothed with separate attack and release times. Detection is done on a single
+/// sidechain value, so the plugin can link the channels by passing in their maximum.
+#[derive(Default)]
+pub struct Compressor {
+    sample_rate: f32,
+    attack_ms: f32,
+    release_ms: f32,
+    attack_weight: f32,
+    release_weight: f32,
+
+    /// The smoothed gain reduction in decibels. This is zero or negative.
+    gain_reduction_db: f32,
+}
+
+impl CompressorCurve {
+    /// The gain reduction for an input level in decibels, following the soft knee formula from
+    /// Giannoulis, Massberg and Reiss. Returns zero or a negative value.
+    fn gain_reduction_db(&self, input_db: f32) -> f32 {
+        let overshoot = input_db - self.threshold_db;
+        let slope = self.ratio.recip() - 1.0;
+
+        if 2.0 * overshoot <= -self.knee_db {
+            0.0
+        } else if 2.0 * overshoot >= self.knee_db {
+            slope * overshoot
+        } else {
+            let knee_overshoot = overshoot + self.knee_db / 2.0;
+            slope * knee_overshoot * knee_overshoot / (2.0 * self.knee_db)
+        }
+    }
+}
+
+impl Compressor {
+    /// Prepare the compressor for a new sample rate. This must be called from `initialize()`,
+    /// followed by a call to [`set_times()`][Self::set_times()].
+    pub fn initialize(&mut self, sample_rate: f32) {
+        self.sample_rate = sample_rate;
+
+        // The next call to `set_times()` recomputes the weights for the new rate
+        self.attack_ms = -1.0;
+        self.release_ms = -1.0;
+        self.reset();
+    }
+
+    pub fn reset(&mut self) {
+        self.gain_reduction_db = 0.0;
+    }
+
+    /// Update the attack and release times. Nothing is recomputed when they haven't changed.
+    pub fn set_times(&mut self, attack_ms: f32, release_ms: f32) {
+        if attack_ms != self.attack_ms {
+            self.attack_ms = attack_ms;
+            self.attack_weight = envelope_weight(self.sample_rate, attack_ms);
+        }
+        if release_ms != self.release_ms {
+            self.release_ms = release_ms;
+            self.release_weight = envelope_weight(self.sample_rate, release_ms);
+        }
+    }
+
+    /// Compute the gain for the next sample from the sidechain's peak value. The returned gain
+    /// includes the makeup gain.
+    pub fn next_gain(&mut self, sidechain_peak: f32, curve: CompressorCurve) -> f32 {
+        let input_db = util::gain_to_db(sidechain_peak);
+        let target_db = curve.gain_reduction_db(input_db);
+
+        // More gain reduction means a lower value, so that's the attack
+        let weight = if target_db < self.gain_reduction_db {
+            self.attack_weight
+        } else {
+            self.release_weight
+        };
+        self.gain_reduction_db = self.gain_reduction_db * weight + target_db * (1.0 - weight);
+
+        util::db_to_gain(self.gain_reduction_db + curve.makeup_db)
+    }
+
+    /// The current gain reduction as a linear gain factor, without the makeup gain.
+    pub fn gain_reduction(&self) -> f32 {
+        util::db_to_gain(self.gain_reduction_db)
+    }
+}
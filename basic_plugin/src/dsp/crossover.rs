@@ -0,0 +1,348 @@
+use nih_plug::prelude::*;
+
+use super::biquad::{Biquad, BiquadCoefficients, BUTTERWORTH_Q};
+
+/// The maximum number of bands the crossover can split the signal into.
+pub const MAX_BANDS: usize = 4;
+const MAX_SPLITS: usize = MAX_BANDS - 1;
+
+/// The Q values for the two biquads making up a fourth order Butterworth filter.
+const BUTTERWORTH_4TH_ORDER_Q: [f32; 2] = [0.541_196_1, 1.306_563];
+/// Neighbouring crossover frequencies are kept at least this far apart.
+const MIN_SPLIT_RATIO: f32 = 1.25;
+
+/// The output of [`Crossover::split()`]. Bands above the configured band count are always zero.
+pub type Bands = [f32; MAX_BANDS];
+
+#[derive(Params)]
+pub struct CrossoverParams {
+    #[id = "xover_bands"]
+    pub num_bands: EnumParam<CrossoverBands>,
+    #[id = "xover_slope"]
+    pub slope: EnumParam<CrossoverSlope>,
+
+    /// The split between the first and the second band.
+    #[id = "xover_freq_1"]
+    pub frequency_1: FloatParam,
+    /// The split between the second and the third band, if there are at least three bands.
+    #[id = "xover_freq_2"]
+    pub frequency_2: FloatParam,
+    /// The split between the third and the fourth band, if there are four bands.
+    #[id = "xover_freq_3"]
+    pub frequency_3: FloatParam,
+}
+
+#[derive(Enum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CrossoverBands {
+    #[name = "2 Bands"]
+    Two,
+    #[name = "3 Bands"]
+    Three,
+    #[name = "4 Bands"]
+    Four,
+}
+
+/// The Linkwitz-Riley filter order. All of these sum back to an all-pass response.
+#[derive(Enum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CrossoverSlope {
+    #[name = "LR12"]
+    Lr12,
+    #[name = "LR24"]
+    Lr24,
+    #[name = "LR48"]
+    Lr48,
+}
+
+impl Default for CrossoverParams {
+    fn default() -> Self {
+        Self {
+            num_bands: EnumParam::new("Bands", CrossoverBands::Two),
+            slope: EnumParam::new("Slope", CrossoverSlope::Lr24),
+
+            frequency_1: crossover_frequency_param("Crossover 1", 120.0, 30.0, 500.0),
+            frequency_2: crossover_frequency_param("Crossover 2", 500.0, 100.0, 2_000.0),
+            frequency_3: crossover_frequency_param("Crossover 3", 2_500.0, 500.0, 10_000.0),
+        }
+    }
+}
+
+impl CrossoverParams {
+    /// The smoothed crossover frequencies for the next sample.
+    pub fn next_frequencies(&self) -> [f32; MAX_SPLITS] {
+        [
+            self.frequency_1.smoothed.next(),
+            self.frequency_2.smoothed.next(),
+            self.frequency_3.smoothed.next(),
+        ]
+    }
+
+    /// The current, unsmoothed crossover frequencies.
+    pub fn frequencies(&self) -> [f32; MAX_SPLITS] {
+        [
+            self.frequency_1.value(),
+            self.frequency_2.value(),
+            self.frequency_3.value(),
+        ]
+    }
+}
+
+fn crossover_frequency_param(name: &str, default: f32, min: f32, max: f32) -> FloatParam {
+    FloatParam::new(
+        name,
+        default,
+        FloatRange::Skewed {
+            min,
+            max,
+            factor: FloatRange::skew_factor(-1.0),
+        },
+    )
+    .with_smoother(SmoothingStyle::Logarithmic(50.0))
+    .with_value_to_string(formatters::v2s_f32_hz_then_khz(0))
+    .with_string_to_value(formatters::s2v_f32_hz_then_khz())
+}
+
+impl CrossoverBands {
+    pub fn count(self) -> usize {
+        match self {
+            CrossoverBands::Two => 2,
+            CrossoverBands::Three => 3,
+            CrossoverBands::Four => 4,
+        }
+    }
+}
+
+/// A Linkwitz-Riley band splitter with up to four bands. The bands are split off one by one from
+/// low to high, and the lower bands are run through the all-pass responses of the higher splits.
+/// That way every band ends up with the same phase response, and the bands sum back to a signal
+/// with a flat magnitude response.
+pub struct Crossover {
+    sample_rate: f32,
+    num_bands: usize,
+    slope: CrossoverSlope,
+    frequencies: [f32; MAX_SPLITS],
+
+    channels: Vec<ChannelState>,
+}
+
+#[derive(Default, Clone)]
+struct ChannelState {
+    splits: [LinkwitzRiley; MAX_SPLITS],
+    /// `compensation[band][split]` is the all-pass for a later split applied to a lower band.
+    /// Only the entries where `split > band` are used.
+    compensation: [[LinkwitzRiley; MAX_SPLITS]; MAX_SPLITS],
+}
+
+/// A matching low-pass and high-pass pair. The high-pass output of the 12 dB/octave variant is
+/// inverted, so for every slope `low + high` is an all-pass.
+#[derive(Default, Clone)]
+struct LinkwitzRiley {
+    lowpass: [Biquad; 4],
+    highpass: [Biquad; 4],
+    num_stages: usize,
+    invert_highpass: bool,
+}
+
+impl Default for Crossover {
+    fn default() -> Self {
+        Self {
+            sample_rate: 0.0,
+            num_bands: 2,
+            slope: CrossoverSlope::Lr24,
+            frequencies: [0.0; MAX_SPLITS],
+
+            channels: Vec::new(),
+        }
+    }
+}
+
+impl Crossover {
+    /// Allocate state for `num_channels` channels. This must be called from `initialize()`,
+    /// followed by a call to [`configure()`][Self::configure()].
+    pub fn initialize(&mut self, num_channels: usize, sample_rate: f32) {
+        self.sample_rate = sample_rate;
+        self.channels
+            .resize_with(num_channels, ChannelState::default);
+
+        // The next call to `configure()` recomputes the filters for the new rate
+        self.frequencies = [0.0; MAX_SPLITS];
+        self.reset();
+    }
+
+    pub fn reset(&mut self) {
+        for channel in &mut self.channels {
+            channel.splits.iter_mut().for_each(LinkwitzRiley::reset);
+            channel
+                .compensation
+                .iter_mut()
+                .flatten()
+                .for_each(LinkwitzRiley::reset);
+        }
+    }
+
+    /// Set the number of bands, the filter slope, and the crossover frequencies. The frequencies
+    /// are kept in ascending order. Filters are only recomputed when something changed, and
+    /// changing the band count or the slope also clears the filter state.
+    pub fn configure(
+        &mut self,
+        num_bands: usize,
+        slope: CrossoverSlope,
+        mut frequencies: [f32; MAX_SPLITS],
+    ) {
+        let num_bands = num_bands.clamp(2, MAX_BANDS);
+        for split_idx in 1..MAX_SPLITS {
+            frequencies[split_idx] =
+                frequencies[split_idx].max(frequencies[split_idx - 1] * MIN_SPLIT_RATIO);
+        }
+
+        let topology_changed = num_bands != self.num_bands || slope != self.slope;
+        if !topology_changed && frequencies == self.frequencies {
+            return;
+        }
+
+        self.num_bands = num_bands;
+        self.slope = slope;
+        self.frequencies = frequencies;
+        self.update_filters(topology_changed);
+    }
+
+    /// Split a sample for a channel into bands. Only the first `num_bands` bands are non-zero.
+    pub fn split(&mut self, channel_idx: usize, sample: f32) -> Bands {
+        let channel = &mut self.channels[channel_idx];
+        let num_splits = self.num_bands - 1;
+
+        let mut bands = [0.0; MAX_BANDS];
+        let mut remainder = sample;
+        for (split_idx, split) in channel.splits[..num_splits].iter_mut().enumerate() {
+            let (low, high) = split.process(remainder);
+            bands[split_idx] = low;
+            remainder = high;
+        }
+        bands[num_splits] = remainder;
+
+        for (band_idx, band) in bands[..num_splits].iter_mut().enumerate() {
+            for allpass in &mut channel.compensation[band_idx][band_idx + 1..num_splits] {
+                let (low, high) = allpass.process(*band);
+                *band = low + high;
+            }
+        }
+
+        bands
+    }
+
+    fn update_filters(&mut self, clear_state: bool) {
+        let num_splits = self.num_bands - 1;
+        for channel in &mut self.channels {
+            for (split_idx, frequency) in self.frequencies[..num_splits].iter().enumerate() {
+                channel.splits[split_idx].configure(self.sample_rate, *frequency, self.slope);
+                for compensation in &mut channel.compensation {
+                    compensation[split_idx].configure(self.sample_rate, *frequency, self.slope);
+                }
+            }
+        }
+
+        if clear_state {
+            self.reset();
+        }
+    }
+}
+
+impl LinkwitzRiley {
+    fn configure(&mut self, sample_rate: f32, frequency: f32, slope: CrossoverSlope) {
+        // A Linkwitz-Riley filter is a Butterworth filter of half the order applied twice. The
+        // 12 dB/octave version is a first order Butterworth squared, which is a single biquad
+        // with a Q of 0.5.
+        let qs: &[f32] = match slope {
+            CrossoverSlope::Lr12 => &[0.5],
+            CrossoverSlope::Lr24 => &[BUTTERWORTH_Q, BUTTERWORTH_Q],
+            CrossoverSlope::Lr48 => &[
+                BUTTERWORTH_4TH_ORDER_Q[0],
+                BUTTERWORTH_4TH_ORDER_Q[1],
+                BUTTERWORTH_4TH_ORDER_Q[0],
+                BUTTERWORTH_4TH_ORDER_Q[1],
+            ],
+        };
+
+        self.num_stages = qs.len();
+        self.invert_highpass = slope == CrossoverSlope::Lr12;
+        for ((lowpass, highpass), q) in self
+            .lowpass
+            .iter_mut()
+            .zip(self.highpass.iter_mut())
+            .zip(qs)
+        {
+            lowpass.coefficients = BiquadCoefficients::lowpass(sample_rate, frequency, *q);
+            highpass.coefficients = BiquadCoefficients::highpass(sample_rate, frequency, *q);
+        }
+    }
+
+    fn reset(&mut self) {
+        self.lowpass.iter_mut().for_each(Biquad::reset);
+        self.highpass.iter_mut().for_each(Biquad::reset);
+    }
+
+    /// Returns the low-pass and high-pass outputs.
+    fn process(&mut self, sample: f32) -> (f32, f32) {
+        let low = self.lowpass[..self.num_stages]
+            .iter_mut()
+            .fold(sample, |sample, filter| filter.process(sample));
+        let high = self.highpass[..self.num_stages]
+            .iter_mut()
+            .fold(sample, |sample, filter| filter.process(sample));
+
+        if self.invert_highpass {
+            (low, -high)
+        } else {
+            (low, high)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f32::consts::PI;
+
+    const SAMPLE_RATE: f32 = 48_000.0;
+
+    /// The RMS level of the summed bands for a sine at `frequency`, measured after the filters
+    /// have settled.
+    fn summed_rms(crossover: &mut Crossover, frequency: f32) -> f32 {
+        crossover.reset();
+
+        let num_samples = SAMPLE_RATE as usize;
+        let mut sum_squares = 0.0;
+        for sample_idx in 0..num_samples {
+            let sample = (2.0 * PI * frequency * sample_idx as f32 / SAMPLE_RATE).sin();
+            let summed: f32 = crossover.split(0, sample).iter().sum();
+            if sample_idx >= num_samples / 2 {
+                sum_squares += summed * summed;
+            }
+        }
+
+        (sum_squares / (num_samples / 2) as f32).sqrt()
+    }
+
+    #[test]
+    fn bands_sum_to_flat_magnitude() {
+        for num_bands in 2..=MAX_BANDS {
+            for slope in [
+                CrossoverSlope::Lr12,
+                CrossoverSlope::Lr24,
+                CrossoverSlope::Lr48,
+            ] {
+                let mut crossover = Crossover::default();
+                crossover.initialize(1, SAMPLE_RATE);
+                crossover.configure(num_bands, slope, [120.0, 500.0, 2_500.0]);
+
+                for frequency in [30.0, 120.0, 300.0, 500.0, 1_000.0, 2_500.0, 8_000.0] {
+                    let rms = summed_rms(&mut crossover, frequency);
+                    let error_db = 20.0 * (rms * 2.0f32.sqrt()).log10();
+                    assert!(
+                        error_db.abs() < 0.05,
+                        "{num_bands} bands, {slope:?}, {frequency} Hz: {error_db:.3} dB"
+                    );
+                }
+            }
+        }
+    }
+}
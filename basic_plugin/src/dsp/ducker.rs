@@ -0,0 +1,151 @@
+use nih_plug::prelude::*;
+
+use super::envelope_weight;
+
+#[derive(Params)]
+pub struct DuckerParams {
+    /// The sidechain level that triggers the ducking.
+    #[id = "duck_threshold"]
+    pub threshold: FloatParam,
+    /// How far the signal is turned down while the sidechain is above the threshold.
+    #[id = "duck_depth"]
+    pub depth: FloatParam,
+    #[id = "duck_attack"]
+    pub attack: FloatParam,
+    /// How long the signal stays ducked after the sidechain drops below the threshold.
+    #[id = "duck_hold"]
+    pub hold: FloatParam,
+    #[id = "duck_release"]
+    pub release: FloatParam,
+}
+
+impl Default for DuckerParams {
+    fn default() -> Self {
+        Self {
+            threshold: FloatParam::new(
+                "Duck Threshold",
+                -24.0,
+                FloatRange::Linear {
+                    min: -60.0,
+                    max: 0.0,
+                },
+            )
+            .with_smoother(SmoothingStyle::Linear(20.0))
+            .with_unit(" dB")
+            .with_step_size(0.1),
+            depth: FloatParam::new(
+                "Duck Depth",
+                0.0,
+                FloatRange::Linear {
+                    min: 0.0,
+                    max: 48.0,
+                },
+            )
+            .with_smoother(SmoothingStyle::Linear(20.0))
+            .with_unit(" dB")
+            .with_step_size(0.1),
+            attack: FloatParam::new(
+                "Duck Attack",
+                2.0,
+                FloatRange::Skewed {
+                    min: 0.1,
+                    max: 50.0,
+                    factor: FloatRange::skew_factor(-2.0),
+                },
+            )
+            .with_unit(" ms")
+            .with_value_to_string(formatters::v2s_f32_rounded(1)),
+            hold: FloatParam::new(
+                "Duck Hold",
+                50.0,
+                FloatRange::Linear {
+                    min: 0.0,
+                    max: 500.0,
+                },
+            )
+            .with_unit(" ms")
+            .with_value_to_string(formatters::v2s_f32_rounded(0)),
+            release: FloatParam::new(
+                "Duck Release",
+                150.0,
+                FloatRange::Skewed {
+                    min: 10.0,
+                    max: 1000.0,
+                    factor: FloatRange::skew_factor(-2.0),
+                },
+            )
+            .with_unit(" ms")
+            .with_value_to_string(formatters::v2s_f32_rounded(0)),
+        }
+    }
+}
+
+/// Turns the signal down while a sidechain signal, usually the kick drum, is above a threshold.
+/// Once triggered the ducking is held for a fixed time before it releases, so the gain doesn't
+/// flutter along with the kick's waveform.
+#[derive(Default)]
+pub struct Ducker {
+    sample_rate: f32,
+    attack_ms: f32,
+    release_ms: f32,
+    attack_weight: f32,
+    release_weight: f32,
+    hold_samples: u32,
+
+    /// The number of samples left before the ducking starts releasing.
+    hold_remaining: u32,
+    /// The smoothed gain reduction in decibels. This is zero or negative.
+    gain_reduction_db: f32,
+}
+
+impl Ducker {
+    /// Prepare the ducker for a new sample rate. This must be called from `initialize()`,
+    /// followed by a call to [`set_times()`][Self::set_times()].
+    pub fn initialize(&mut self, sample_rate: f32) {
+        self.sample_rate = sample_rate;
+
+        // The next call to `set_times()` recomputes the weights for the new rate
+        self.attack_ms = -1.0;
+        self.release_ms = -1.0;
+        self.reset();
+    }
+
+    pub fn reset(&mut self) {
+        self.hold_remaining = 0;
+        self.gain_reduction_db = 0.0;
+    }
+
+    /// Update the envelope times. Nothing is recomputed when the attack and release times
+    /// haven't changed.
+    pub fn set_times(&mut self, attack_ms: f32, hold_ms: f32, release_ms: f32) {
+        if attack_ms != self.attack_ms {
+            self.attack_ms = attack_ms;
+            self.attack_weight = envelope_weight(self.sample_rate, attack_ms);
+        }
+        if release_ms != self.release_ms {
+            self.release_ms = release_ms;
+            self.release_weight = envelope_weight(self.sample_rate, release_ms);
+        }
+        self.hold_samples = (self.sample_rate * hold_ms / 1000.0).round() as u32;
+    }
+
+    /// Compute the gain for the next sample from the sidechain's peak value.
+    pub fn next_gain(&mut self, sidechain_peak: f32, threshold_db: f32, depth_db: f32) -> f32 {
+        let above_threshold = util::gain_to_db(sidechain_peak) >= threshold_db;
+        if above_threshold {
+            self.hold_remaining = self.hold_samples;
+        } else {
+            self.hold_remaining = self.hold_remaining.saturating_sub(1);
+        }
+
+        let triggered = above_threshold || self.hold_remaining > 0;
+        let (target_db, weight) = if triggered {
+            (-depth_db, self.attack_weight)
+        } else {
+            (0.0, self.release_weight)
+        };
+        self.gain_reduction_db = self.gain_reduction_db * weight + target_db * (1.0 - weight);
+
+        util::db_to_gain(self.gain_reduction_db)
+    }
+}
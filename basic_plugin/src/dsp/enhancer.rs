@@ -0,0 +1,134 @@
+use super::biquad::{Biquad, BiquadCoefficients, BUTTERWORTH_Q};
+use super::envelope_weight;
+
+/// Relative levels of the 2nd, 3rd and 4th harmonics. These roll off like a natural harmonic
+/// series so the result reads as the same note rather than as distortion.
+const HARMONIC_WEIGHTS: [f32; 3] = [0.6, 0.3, 0.1];
+/// The generated harmonics are band limited to this multiple of the crossover frequency.
+const HARMONICS_UPPER_LIMIT: f32 = 6.0;
+
+/// The envelope holds its peak for longer than the period of the lowest note on a five string
+/// bass, so a steady note keeps a constant envelope, and then falls back.
+const ENVELOPE_HOLD_MS: f32 = 50.0;
+const ENVELOPE_RELEASE_MS: f32 = 80.0;
+/// Keeps the envelope normalization from dividing by (almost) zero during silence.
+const ENVELOPE_FLOOR: f32 = 1e-4;
+
+/// A psychoacoustic bass enhancer. The band split's low band is normalized by its peak envelope and
+/// run through Chebyshev polynomials so a sine at the fundamental turns into its exact upper
+/// harmonics. Those harmonics are then scaled back to the low band's level and blended into the
+/// signal, which makes the fundamental audible on speakers that can't reproduce it.
+#[derive(Default)]
+pub struct Enhancer {
+    sample_rate: f32,
+    crossover_frequency: f32,
+
+    envelope_hold_samples: usize,
+    envelope_release_weight: f32,
+
+    channels: Vec<ChannelState>,
+}
+
+#[derive(Default, Clone)]
+struct ChannelState {
+    /// The low band's peak level. This is never below the low band's current sample.
+    envelope: f32,
+    /// The number of samples left before the envelope starts to fall.
+    envelope_hold_counter: usize,
+    /// Removes the fundamental and any DC offset from the generated harmonics.
+    harmonics_highpass: Biquad,
+    /// Keeps the higher order intermodulation products out of the mids.
+    harmonics_lowpass: Biquad,
+}
+
+impl Enhancer {
+    /// Allocate state for `num_channels` channels. This must be called from `initialize()`,
+    /// followed by a call to [`set_crossover_frequency()`][Self::set_crossover_frequency()].
+    pub fn initialize(&mut self, num_channels: usize, sample_rate: f32) {
+        self.sample_rate = sample_rate;
+        self.envelope_hold_samples = (sample_rate * ENVELOPE_HOLD_MS / 1000.0).round() as usize;
+        self.envelope_release_weight = envelope_weight(sample_rate, ENVELOPE_RELEASE_MS);
+        self.channels
+            .resize_with(num_channels, ChannelState::default);
+
+        // The next call to `set_crossover_frequency()` recomputes the filters for the new rate
+        self.crossover_frequency = 0.0;
+    }
+
+    pub fn reset(&mut self) {
+        for channel in &mut self.channels {
+            channel.envelope = 0.0;
+            channel.envelope_hold_counter = 0;
+            channel.harmonics_highpass.reset();
+            channel.harmonics_lowpass.reset();
+        }
+    }
+
+    /// Update the harmonics filters for a new low band crossover frequency. This is cheap to call
+    /// every sample since nothing is recomputed when the frequency hasn't changed.
+    pub fn set_crossover_frequency(&mut self, frequency: f32) {
+        if frequency == self.crossover_frequency {
+            return;
+        }
+        self.crossover_frequency = frequency;
+
+        let harmonics_highpass =
+            BiquadCoefficients::highpass(self.sample_rate, frequency * 1.5, BUTTERWORTH_Q);
+        let harmonics_lowpass = BiquadCoefficients::lowpass(
+            self.sample_rate,
+            frequency * HARMONICS_UPPER_LIMIT,
+            BUTTERWORTH_Q,
+        );
+        for channel in &mut self.channels {
+            channel.harmonics_highpass.coefficients = harmonics_highpass;
+            channel.harmonics_lowpass.coefficients = harmonics_lowpass;
+        }
+    }
+
+    /// Process a single sample for a channel. `low` is that sample's low band from the band split,
+    /// which the harmonics are generated from. `amount` controls how far up the harmonic series the
+    /// generator reaches, with 0 only producing the 2nd harmonic. `blend` is the level of the
+    /// harmonics mixed back in with the dry signal.
+    pub fn process(
+        &mut self,
+        channel_idx: usize,
+        sample: f32,
+        low: f32,
+        amount: f32,
+        blend: f32,
+    ) -> f32 {
+        let channel = &mut self.channels[channel_idx];
+
+        let rectified = low.abs();
+        if rectified >= channel.envelope {
+            channel.envelope = rectified;
+            channel.envelope_hold_counter = self.envelope_hold_samples;
+        } else if channel.envelope_hold_counter > 0 {
+            channel.envelope_hold_counter -= 1;
+        } else {
+            channel.envelope = (channel.envelope * self.envelope_release_weight).max(rectified);
+        }
+
+        // With the peak envelope divided out a steady bass note is a unit sine, which is exactly
+        // what the Chebyshev polynomials need to produce clean harmonics. The envelope never
+        // drops below the low band, so this stays within [-1, 1].
+        let normalized = low / channel.envelope.max(ENVELOPE_FLOOR);
+        let harmonics = chebyshev_harmonics(normalized, amount) * channel.envelope;
+        let harmonics = channel
+            .harmonics_lowpass
+            .process(channel.harmonics_highpass.process(harmonics));
+
+        sample + harmonics * blend
+    }
+}
+
+/// Weighted sum of the Chebyshev polynomials `T2` through `T4`, with the 3rd and 4th harmonics
+/// scaled by `amount`. For `x = cos(θ)`, `Tn(x)` equals `cos(nθ)`.
+fn chebyshev_harmonics(x: f32, amount: f32) -> f32 {
+    let x2 = x * x;
+    let t2 = 2.0 * x2 - 1.0;
+    let t3 = (4.0 * x2 - 3.0) * x;
+    let t4 = 8.0 * x2 * x2 - 8.0 * x2 + 1.0;
+
+    HARMONIC_WEIGHTS[0] * t2 + (HARMONIC_WEIGHTS[1] * t3 + HARMONIC_WEIGHTS[2] * t4) * amount
+}
@@ -0,0 +1,300 @@
+use nih_plug::prelude::*;
+
+use super::biquad::{Biquad, BiquadCoefficients, BUTTERWORTH_Q};
+
+/// The number of bands in the [`Equalizer`], from the low-cut up to the high-cut.
+pub const NUM_EQ_BANDS: usize = 7;
+
+/// The filter shape of every band, in the same order as the bands in [`EqParams`].
+const BAND_TYPES: [BandType; NUM_EQ_BANDS] = [
+    BandType::LowCut,
+    BandType::LowShelf,
+    BandType::Peak,
+    BandType::Peak,
+    BandType::Peak,
+    BandType::HighShelf,
+    BandType::HighCut,
+];
+
+#[derive(Params)]
+pub struct EqParams {
+    #[nested(id_prefix = "eq_low_cut", group = "Low Cut")]
+    pub low_cut: EqBandParams,
+    #[nested(id_prefix = "eq_low_shelf", group = "Low Shelf")]
+    pub low_shelf: EqBandParams,
+    #[nested(id_prefix = "eq_peak_1", group = "Peak 1")]
+    pub peak_1: EqBandParams,
+    #[nested(id_prefix = "eq_peak_2", group = "Peak 2")]
+    pub peak_2: EqBandParams,
+    #[nested(id_prefix = "eq_peak_3", group = "Peak 3")]
+    pub peak_3: EqBandParams,
+    #[nested(id_prefix = "eq_high_shelf", group = "High Shelf")]
+    pub high_shelf: EqBandParams,
+    #[nested(id_prefix = "eq_high_cut", group = "High Cut")]
+    pub high_cut: EqBandParams,
+}
+
+/// The parameters for a single EQ band. The cut filters don't have a gain, so their gain
+/// parameter is hidden from the editor.
+#[derive(Params)]
+pub struct EqBandParams {
+    #[id = "enabled"]
+    pub enabled: BoolParam,
+    #[id = "freq"]
+    pub frequency: FloatParam,
+    #[id = "gain"]
+    pub gain: FloatParam,
+    #[id = "q"]
+    pub q: FloatParam,
+}
+
+/// A band's parameter values for a single sample.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EqBandSettings {
+    pub enabled: bool,
+    pub frequency: f32,
+    pub gain_db: f32,
+    pub q: f32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BandType {
+    LowCut,
+    LowShelf,
+    Peak,
+    HighShelf,
+    HighCut,
+}
+
+impl Default for EqParams {
+    fn default() -> Self {
+        Self {
+            low_cut: EqBandParams::new("Low Cut", BandType::LowCut, false, 30.0, 10.0, 1_000.0),
+            low_shelf: EqBandParams::new(
+                "Low Shelf",
+                BandType::LowShelf,
+                true,
+                100.0,
+                20.0,
+                1_000.0,
+            ),
+            peak_1: EqBandParams::new("Peak 1", BandType::Peak, true, 250.0, 20.0, 20_000.0),
+            peak_2: EqBandParams::new("Peak 2", BandType::Peak, true, 1_000.0, 20.0, 20_000.0),
+            peak_3: EqBandParams::new("Peak 3", BandType::Peak, true, 4_000.0, 20.0, 20_000.0),
+            high_shelf: EqBandParams::new(
+                "High Shelf",
+                BandType::HighShelf,
+                true,
+                8_000.0,
+                1_000.0,
+                20_000.0,
+            ),
+            high_cut: EqBandParams::new(
+                "High Cut",
+                BandType::HighCut,
+                false,
+                18_000.0,
+                1_000.0,
+                20_000.0,
+            ),
+        }
+    }
+}
+
+impl EqParams {
+    /// The smoothed settings for every band for the next sample.
+    pub fn next_settings(&self) -> [EqBandSettings; NUM_EQ_BANDS] {
+        self.bands().map(EqBandParams::next_settings)
+    }
+
+    /// The current, unsmoothed settings for every band.
+    pub fn settings(&self) -> [EqBandSettings; NUM_EQ_BANDS] {
+        self.bands().map(EqBandParams::settings)
+    }
+
+    fn bands(&self) -> [&EqBandParams; NUM_EQ_BANDS] {
+        [
+            &self.low_cut,
+            &self.low_shelf,
+            &self.peak_1,
+            &self.peak_2,
+            &self.peak_3,
+            &self.high_shelf,
+            &self.high_cut,
+        ]
+    }
+}
+
+impl EqBandParams {
+    fn new(
+        name: &str,
+        band_type: BandType,
+        enabled: bool,
+        default_frequency: f32,
+        min_frequency: f32,
+        max_frequency: f32,
+    ) -> Self {
+        let is_cut = matches!(band_type, BandType::LowCut | BandType::HighCut);
+        let default_q = if band_type == BandType::Peak {
+            1.0
+        } else {
+            BUTTERWORTH_Q
+        };
+
+        let gain = FloatParam::new(
+            format!("{name} Gain"),
+            0.0,
+            FloatRange::Linear {
+                min: -18.0,
+                max: 18.0,
+            },
+        )
+        .with_smoother(SmoothingStyle::Linear(20.0))
+        .with_unit(" dB")
+        .with_step_size(0.1);
+
+        Self {
+            enabled: BoolParam::new(format!("{name} Enabled"), enabled),
+            frequency: FloatParam::new(
+                format!("{name} Frequency"),
+                default_frequency,
+                FloatRange::Skewed {
+                    min: min_frequency,
+                    max: max_frequency,
+                    factor: FloatRange::skew_factor(-2.0),
+                },
+            )
+            .with_smoother(SmoothingStyle::Logarithmic(50.0))
+            .with_value_to_string(formatters::v2s_f32_hz_then_khz(0))
+            .with_string_to_value(formatters::s2v_f32_hz_then_khz()),
+            gain: if is_cut {
+                gain.hide_in_generic_ui()
+            } else {
+                gain
+            },
+            q: FloatParam::new(
+                format!("{name} Q"),
+                default_q,
+                FloatRange::Skewed {
+                    min: 0.1,
+                    max: 10.0,
+                    factor: FloatRange::skew_factor(-2.0),
+                },
+            )
+            .with_smoother(SmoothingStyle::Logarithmic(50.0))
+            .with_value_to_string(formatters::v2s_f32_rounded(2)),
+        }
+    }
+
+    fn next_settings(&self) -> EqBandSettings {
+        EqBandSettings {
+            enabled: self.enabled.value(),
+            frequency: self.frequency.smoothed.next(),
+            gain_db: self.gain.smoothed.next(),
+            q: self.q.smoothed.next(),
+        }
+    }
+
+    fn settings(&self) -> EqBandSettings {
+        EqBandSettings {
+            enabled: self.enabled.value(),
+            frequency: self.frequency.value(),
+            gain_db: self.gain.value(),
+            q: self.q.value(),
+        }
+    }
+}
+
+/// A parametric EQ with a low-cut, a low shelf, three peaking bells, a high shelf, and a
+/// high-cut, all in series. Disabled bands are skipped entirely.
+#[derive(Default)]
+pub struct Equalizer {
+    sample_rate: f32,
+    settings: [Option<EqBandSettings>; NUM_EQ_BANDS],
+
+    channels: Vec<ChannelState>,
+}
+
+#[derive(Default)]
+struct ChannelState {
+    filters: [Biquad; NUM_EQ_BANDS],
+}
+
+impl Equalizer {
+    /// Allocate state for `num_channels` channels. This must be called from `initialize()`,
+    /// followed by a call to [`configure()`][Self::configure()].
+    pub fn initialize(&mut self, num_channels: usize, sample_rate: f32) {
+        self.sample_rate = sample_rate;
+        self.channels
+            .resize_with(num_channels, ChannelState::default);
+
+        // The next call to `configure()` recomputes the filters for the new rate
+        self.settings = [None; NUM_EQ_BANDS];
+        self.reset();
+    }
+
+    pub fn reset(&mut self) {
+        for channel in &mut self.channels {
+            channel.filters.iter_mut().for_each(Biquad::reset);
+        }
+    }
+
+    /// Update the bands. Only the bands whose settings changed are recomputed, and a band's
+    /// filter state is cleared when it gets enabled so it doesn't start with stale samples.
+    pub fn configure(&mut self, settings: [EqBandSettings; NUM_EQ_BANDS]) {
+        for (band_idx, new_settings) in settings.into_iter().enumerate() {
+            let old_settings = self.settings[band_idx];
+            if old_settings == Some(new_settings) {
+                continue;
+            }
+            self.settings[band_idx] = Some(new_settings);
+            if !new_settings.enabled {
+                continue;
+            }
+
+            let coefficients =
+                band_coefficients(self.sample_rate, BAND_TYPES[band_idx], new_settings);
+            let was_enabled = old_settings.is_some_and(|settings| settings.enabled);
+            for channel in &mut self.channels {
+                let filter = &mut channel.filters[band_idx];
+                filter.coefficients = coefficients;
+                if !was_enabled {
+                    filter.reset();
+                }
+            }
+        }
+    }
+
+    /// Filter a single sample for a channel through all enabled bands.
+    pub fn process(&mut self, channel_idx: usize, sample: f32) -> f32 {
+        let channel = &mut self.channels[channel_idx];
+
+        channel
+            .filters
+            .iter_mut()
+            .zip(&self.settings)
+            .filter(|(_, settings)| settings.is_some_and(|settings| settings.enabled))
+            .fold(sample, |sample, (filter, _)| filter.process(sample))
+    }
+}
+
+fn band_coefficients(
+    sample_rate: f32,
+    band_type: BandType,
+    settings: EqBandSettings,
+) -> BiquadCoefficients {
+    let EqBandSettings {
+        frequency,
+        gain_db,
+        q,
+        ..
+    } = settings;
+
+    match band_type {
+        BandType::LowCut => BiquadCoefficients::highpass(sample_rate, frequency, q),
+        BandType::LowShelf => BiquadCoefficients::low_shelf(sample_rate, frequency, q, gain_db),
+        BandType::Peak => BiquadCoefficients::peaking(sample_rate, frequency, q, gain_db),
+        BandType::HighShelf => BiquadCoefficients::high_shelf(sample_rate, frequency, q, gain_db),
+        BandType::HighCut => BiquadCoefficients::lowpass(sample_rate, frequency, q),
+    }
+}
@@ -0,0 +1,73 @@
+use std::f32::consts::PI;
+
+/// An in-place radix-2 FFT for a fixed power of two size. The twiddle factors and the bit reversal
+/// permutation are computed up front, so transforming doesn't allocate.
+pub struct Fft {
+    size: usize,
+    /// `(cos, sin)` of `-2 pi k / size` for the first half of the unit circle.
+    twiddles: Vec<(f32, f32)>,
+    bit_reversed: Vec<usize>,
+}
+
+impl Fft {
+    /// Plan a transform of `size` points. `size` must be a power of two.
+    pub fn new(size: usize) -> Self {
+        assert!(size.is_power_of_two());
+
+        let twiddles = (0..size / 2)
+            .map(|k| {
+                let angle = -2.0 * PI * k as f32 / size as f32;
+                (angle.cos(), angle.sin())
+            })
+            .collect();
+        let num_bits = size.trailing_zeros();
+        let bit_reversed = (0..size)
+            .map(|idx| {
+                idx.reverse_bits()
+                    .checked_shr(usize::BITS - num_bits)
+                    .unwrap_or(0)
+            })
+            .collect();
+
+        Self {
+            size,
+            twiddles,
+            bit_reversed,
+        }
+    }
+
+    /// Transform the complex signal in `real` and `imaginary` to the frequency domain. Both slices
+    /// must be exactly as long as the planned size. The result is not normalized.
+    pub fn process(&self, real: &mut [f32], imaginary: &mut [f32]) {
+        assert_eq!(real.len(), self.size);
+        assert_eq!(imaginary.len(), self.size);
+
+        for (idx, reversed_idx) in self.bit_reversed.iter().copied().enumerate() {
+            if idx < reversed_idx {
+                real.swap(idx, reversed_idx);
+                imaginary.swap(idx, reversed_idx);
+            }
+        }
+
+        let mut half_length = 1;
+        while half_length < self.size {
+            let twiddle_stride = self.size / (half_length * 2);
+            for start in (0..self.size).step_by(half_length * 2) {
+                for offset in 0..half_length {
+                    let (cos, sin) = self.twiddles[offset * twiddle_stride];
+                    let even = start + offset;
+                    let odd = even + half_length;
+
+                    let odd_real = real[odd] * cos - imaginary[odd] * sin;
+                    let odd_imaginary = real[odd] * sin + imaginary[odd] * cos;
+                    real[odd] = real[even] - odd_real;
+                    imaginary[odd] = imaginary[even] - odd_imaginary;
+                    real[even] += odd_real;
+                    imaginary[even] += odd_imaginary;
+                }
+            }
+
+            half_length *= 2;
+        }
+    }
+}
@@ -0,0 +1,286 @@
+use nih_plug::prelude::*;
+
+use super::biquad::{Biquad, BiquadCoefficients, BUTTERWORTH_Q};
+use super::envelope_weight;
+
+/// The release time of the key's envelope. The envelope follows rising peaks instantly, and this
+/// is slow enough to ride over the zero crossings of the lowest bass notes. The hysteresis takes
+/// care of the remaining ripple.
+const KEY_RELEASE_MS: f32 = 20.0;
+
+#[derive(Params)]
+pub struct GateParams {
+    /// The gate opens when the key signal rises above this level.
+    #[id = "gate_threshold"]
+    pub threshold: FloatParam,
+    /// How far the signal is turned down while the gate is closed. A small range makes the gate
+    /// act like a gentle downward expander. This defaults to 0 dB, so the gate is off until a
+    /// range is dialed in.
+    #[id = "gate_range"]
+    pub range: FloatParam,
+    /// The gate only closes again once the key signal drops this far below the threshold, so
+    /// levels hovering around the threshold don't make it chatter.
+    #[id = "gate_hysteresis"]
+    pub hysteresis: FloatParam,
+    #[id = "gate_attack"]
+    pub attack: FloatParam,
+    /// How long the gate stays open after the key signal drops below the closing level.
+    #[id = "gate_hold"]
+    pub hold: FloatParam,
+    #[id = "gate_release"]
+    pub release: FloatParam,
+
+    /// Filters the signal the gate listens to, without affecting the audio.
+    #[id = "gate_key_filter"]
+    pub key_filter: EnumParam<GateKeyFilter>,
+    #[id = "gate_key_freq"]
+    pub key_frequency: FloatParam,
+}
+
+#[derive(Enum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GateKeyFilter {
+    #[name = "Off"]
+    Off,
+    /// Keeps hum and rumble from holding the gate open.
+    #[name = "High-Pass"]
+    Highpass,
+    /// Keeps finger and fret noise from opening the gate.
+    #[name = "Low-Pass"]
+    Lowpass,
+    #[name = "Band-Pass"]
+    Bandpass,
+}
+
+impl Default for GateParams {
+    fn default() -> Self {
+        Self {
+            threshold: FloatParam::new(
+                "Gate Threshold",
+                -80.0,
+                FloatRange::Linear {
+                    min: -80.0,
+                    max: 0.0,
+                },
+            )
+            .with_smoother(SmoothingStyle::Linear(20.0))
+            .with_unit(" dB")
+            .with_step_size(0.1),
+            range: FloatParam::new(
+                "Gate Range",
+                0.0,
+                FloatRange::Linear {
+                    min: 0.0,
+                    max: 80.0,
+                },
+            )
+            .with_smoother(SmoothingStyle::Linear(20.0))
+            .with_unit(" dB")
+            .with_step_size(0.1),
+            hysteresis: FloatParam::new(
+                "Gate Hysteresis",
+                4.0,
+                FloatRange::Linear {
+                    min: 0.0,
+                    max: 20.0,
+                },
+            )
+            .with_unit(" dB")
+            .with_step_size(0.1),
+            attack: FloatParam::new(
+                "Gate Attack",
+                0.5,
+                FloatRange::Skewed {
+                    min: 0.01,
+                    max: 50.0,
+                    factor: FloatRange::skew_factor(-2.0),
+                },
+            )
+            .with_unit(" ms")
+            .with_value_to_string(formatters::v2s_f32_rounded(2)),
+            hold: FloatParam::new(
+                "Gate Hold",
+                20.0,
+                FloatRange::Linear {
+                    min: 0.0,
+                    max: 500.0,
+                },
+            )
+            .with_unit(" ms")
+            .with_value_to_string(formatters::v2s_f32_rounded(0)),
+            release: FloatParam::new(
+                "Gate Release",
+                100.0,
+                FloatRange::Skewed {
+                    min: 5.0,
+                    max: 2000.0,
+                    factor: FloatRange::skew_factor(-2.0),
+                },
+            )
+            .with_unit(" ms")
+            .with_value_to_string(formatters::v2s_f32_rounded(0)),
+
+            key_filter: EnumParam::new("Key Filter", GateKeyFilter::Off),
+            key_frequency: FloatParam::new(
+                "Key Frequency",
+                80.0,
+                FloatRange::Skewed {
+                    min: 20.0,
+                    max: 5_000.0,
+                    factor: FloatRange::skew_factor(-2.0),
+                },
+            )
+            .with_smoother(SmoothingStyle::Logarithmic(50.0))
+            .with_value_to_string(formatters::v2s_f32_hz_then_khz(0))
+            .with_string_to_value(formatters::s2v_f32_hz_then_khz()),
+        }
+    }
+}
+
+/// The gate's levels for a single sample. These are read from the smoothed parameter values.
+#[derive(Debug, Clone, Copy)]
+pub struct GateLevels {
+    pub threshold_db: f32,
+    pub range_db: f32,
+    pub hysteresis_db: f32,
+}
+
+/// A noise gate with hysteresis, a hold time, and a filtered key signal. Every channel's key is
+/// filtered separately, and the plugin links the channels by passing the loudest key to
+/// [`next_gain()`][Self::next_gain()]. The gate opens and closes based on a peak envelope of that
+/// key, so it doesn't close at every zero crossing of a sustained note.
+#[derive(Default)]
+pub struct Gate {
+    sample_rate: f32,
+    attack_ms: f32,
+    release_ms: f32,
+    attack_weight: f32,
+    release_weight: f32,
+    hold_samples: u32,
+    key_filter: Option<(GateKeyFilter, f32)>,
+    key_release_weight: f32,
+
+    /// The linked key's peak envelope.
+    key_envelope: f32,
+    open: bool,
+    /// The number of samples left before the gate starts closing.
+    hold_remaining: u32,
+    /// The smoothed gain reduction in decibels. This is zero or negative.
+    gain_reduction_db: f32,
+
+    channels: Vec<ChannelState>,
+}
+
+#[derive(Default)]
+struct ChannelState {
+    key_filter: Biquad,
+}
+
+impl Gate {
+    /// Allocate state for `num_channels` channels. This must be called from `initialize()`,
+    /// followed by calls to [`set_times()`][Self::set_times()] and
+    /// [`set_key_filter()`][Self::set_key_filter()].
+    pub fn initialize(&mut self, num_channels: usize, sample_rate: f32) {
+        self.sample_rate = sample_rate;
+        self.key_release_weight = envelope_weight(sample_rate, KEY_RELEASE_MS);
+        self.channels
+            .resize_with(num_channels, ChannelState::default);
+
+        // The next calls to `set_times()` and `set_key_filter()` recompute everything for the new
+        // rate
+        self.attack_ms = -1.0;
+        self.release_ms = -1.0;
+        self.key_filter = None;
+        self.reset();
+    }
+
+    pub fn reset(&mut self) {
+        for channel in &mut self.channels {
+            channel.key_filter.reset();
+        }
+
+        // The gate starts out open so the first note doesn't get cut off
+        self.key_envelope = 0.0;
+        self.open = true;
+        self.hold_remaining = 0;
+        self.gain_reduction_db = 0.0;
+    }
+
+    /// Update the envelope times. Nothing is recomputed when the attack and release times
+    /// haven't changed.
+    pub fn set_times(&mut self, attack_ms: f32, hold_ms: f32, release_ms: f32) {
+        if attack_ms != self.attack_ms {
+            self.attack_ms = attack_ms;
+            self.attack_weight = envelope_weight(self.sample_rate, attack_ms);
+        }
+        if release_ms != self.release_ms {
+            self.release_ms = release_ms;
+            self.release_weight = envelope_weight(self.sample_rate, release_ms);
+        }
+        self.hold_samples = (self.sample_rate * hold_ms / 1000.0).round() as u32;
+    }
+
+    /// Update the key filter. Nothing is recomputed when it hasn't changed, and changing the
+    /// filter type also clears the filter state.
+    pub fn set_key_filter(&mut self, filter: GateKeyFilter, frequency: f32) {
+        if self.key_filter == Some((filter, frequency)) {
+            return;
+        }
+        let type_changed = self.key_filter.map(|(filter, _)| filter) != Some(filter);
+        self.key_filter = Some((filter, frequency));
+
+        let coefficients = match filter {
+            GateKeyFilter::Off => BiquadCoefficients::identity(),
+            GateKeyFilter::Highpass => {
+                BiquadCoefficients::highpass(self.sample_rate, frequency, BUTTERWORTH_Q)
+            }
+            GateKeyFilter::Lowpass => {
+                BiquadCoefficients::lowpass(self.sample_rate, frequency, BUTTERWORTH_Q)
+            }
+            GateKeyFilter::Bandpass => {
+                BiquadCoefficients::bandpass(self.sample_rate, frequency, BUTTERWORTH_Q)
+            }
+        };
+        for channel in &mut self.channels {
+            channel.key_filter.coefficients = coefficients;
+            if type_changed {
+                channel.key_filter.reset();
+            }
+        }
+    }
+
+    /// Run a channel's sample through the key filter, and return the filtered key's absolute
+    /// value.
+    pub fn key(&mut self, channel_idx: usize, sample: f32) -> f32 {
+        self.channels[channel_idx].key_filter.process(sample).abs()
+    }
+
+    /// Compute the gain for the next sample from the loudest filtered key value.
+    pub fn next_gain(&mut self, key_peak: f32, levels: GateLevels) -> f32 {
+        self.key_envelope = if key_peak > self.key_envelope {
+            key_peak
+        } else {
+            self.key_envelope * self.key_release_weight + key_peak * (1.0 - self.key_release_weight)
+        };
+
+        let key_db = util::gain_to_db(self.key_envelope);
+        if key_db >= levels.threshold_db {
+            self.open = true;
+            self.hold_remaining = self.hold_samples;
+        } else if key_db < levels.threshold_db - levels.hysteresis_db {
+            if self.hold_remaining > 0 {
+                self.hold_remaining -= 1;
+            } else {
+                self.open = false;
+            }
+        }
+
+        let (target_db, weight) = if self.open {
+            (0.0, self.attack_weight)
+        } else {
+            (-levels.range_db, self.release_weight)
+        };
+        self.gain_reduction_db = self.gain_reduction_db * weight + target_db * (1.0 - weight);
+
+        util::db_to_gain(self.gain_reduction_db)
+    }
+}
@@ -0,0 +1,100 @@
+use super::biquad::{Biquad, BiquadCoefficients};
+
+/// The two stage K-weighting filter from ITU-R BS.1770 for a single channel. Loudness
+/// measurements square and average the filtered signal.
+#[derive(Default, Clone)]
+pub struct KWeighting {
+    /// Models the acoustic effect of the head.
+    shelf: Biquad,
+    /// The revised low-frequency B-curve.
+    highpass: Biquad,
+}
+
+impl KWeighting {
+    pub fn set_sample_rate(&mut self, sample_rate: f32) {
+        let (shelf, highpass) = coefficients(sample_rate);
+        self.shelf.coefficients = shelf;
+        self.highpass.coefficients = highpass;
+    }
+
+    pub fn process(&mut self, sample: f32) -> f32 {
+        self.highpass.process(self.shelf.process(sample))
+    }
+
+    pub fn reset(&mut self) {
+        self.shelf.reset();
+        self.highpass.reset();
+    }
+}
+
+/// The K-weighting filter's coefficients for any sample rate, from the analog prototype the
+/// 48 kHz coefficients in BS.1770 were derived from.
+fn coefficients(sample_rate: f32) -> (BiquadCoefficients, BiquadCoefficients) {
+    let sample_rate = sample_rate as f64;
+
+    let frequency = 1_681.974_450_955_533;
+    let gain_db = 3.999_843_853_973_347;
+    let q = 0.707_175_236_955_419_6;
+    let k = (std::f64::consts::PI * frequency / sample_rate).tan();
+    let vh = 10.0f64.powf(gain_db / 20.0);
+    let vb = vh.powf(0.499_666_774_154_541_6);
+    let shelf = BiquadCoefficients::from_raw(
+        (vh + vb * k / q + k * k) as f32,
+        (2.0 * (k * k - vh)) as f32,
+        (vh - vb * k / q + k * k) as f32,
+        (1.0 + k / q + k * k) as f32,
+        (2.0 * (k * k - 1.0)) as f32,
+        (1.0 - k / q + k * k) as f32,
+    );
+
+    // Unlike the shelf, BS.1770 only normalizes the high-pass' denominator and leaves its
+    // numerator at [1, -2, 1]. `from_raw()` divides by `a0`, so the numerator is scaled up first.
+    let frequency = 38.135_470_876_024_44;
+    let q = 0.500_327_037_323_877_3;
+    let k = (std::f64::consts::PI * frequency / sample_rate).tan();
+    let a0 = 1.0 + k / q + k * k;
+    let highpass = BiquadCoefficients::from_raw(
+        a0 as f32,
+        (-2.0 * a0) as f32,
+        a0 as f32,
+        a0 as f32,
+        (2.0 * (k * k - 1.0)) as f32,
+        (1.0 - k / q + k * k) as f32,
+    );
+
+    (shelf, highpass)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_the_published_48k_coefficients() {
+        // The 48 kHz coefficients from ITU-R BS.1770-4, Tables 1 and 2
+        let mut shelf = Biquad::default();
+        shelf.coefficients = BiquadCoefficients::from_raw(
+            1.535_124_9,
+            -2.691_696_2,
+            1.198_392_8,
+            1.0,
+            -1.690_659_3,
+            0.732_480_8,
+        );
+        let mut highpass = Biquad::default();
+        highpass.coefficients =
+            BiquadCoefficients::from_raw(1.0, -2.0, 1.0, 1.0, -1.990_047_5, 0.990_072_25);
+
+        let mut filter = KWeighting::default();
+        filter.set_sample_rate(48_000.0);
+        for sample_idx in 0..4_800 {
+            let sample = if sample_idx == 0 { 1.0 } else { 0.0 };
+            let expected = highpass.process(shelf.process(sample));
+            let actual = filter.process(sample);
+            assert!(
+                (actual - expected).abs() < 1e-5,
+                "sample {sample_idx}: {actual} != {expected}"
+            );
+        }
+    }
+}
@@ -0,0 +1,425 @@
+use nih_plug::prelude::*;
+
+use super::envelope_weight;
+use super::true_peak::{TruePeakDetector, TRUE_PEAK_LATENCY};
+
+/// The longest lookahead time. The delay lines are allocated for this length.
+pub const MAX_LOOKAHEAD_MS: f32 = 10.0;
+
+#[derive(Params)]
+pub struct LimiterParams {
+    /// The output never goes above this level.
+    #[id = "lim_ceiling"]
+    pub ceiling: FloatParam,
+    #[id = "lim_release"]
+    pub release: FloatParam,
+    /// How far ahead the limiter looks, which is also the latency it adds.
+    #[id = "lim_lookahead"]
+    pub lookahead: FloatParam,
+    /// Detect the peaks between samples instead of only the sample values. This adds a few samples
+    /// of latency.
+    #[id = "lim_true_peak"]
+    pub true_peak: BoolParam,
+}
+
+impl Default for LimiterParams {
+    fn default() -> Self {
+        Self {
+            ceiling: FloatParam::new(
+                "Ceiling",
+                -0.3,
+                FloatRange::Linear {
+                    min: -24.0,
+                    max: 0.0,
+                },
+            )
+            .with_smoother(SmoothingStyle::Linear(20.0))
+            .with_unit(" dB")
+            .with_step_size(0.1),
+            release: FloatParam::new(
+                "Limiter Release",
+                100.0,
+                FloatRange::Skewed {
+                    min: 1.0,
+                    max: 1000.0,
+                    factor: FloatRange::skew_factor(-2.0),
+                },
+            )
+            .with_unit(" ms")
+            .with_value_to_string(formatters::v2s_f32_rounded(0)),
+            lookahead: FloatParam::new(
+                "Lookahead",
+                5.0,
+                FloatRange::Linear {
+                    min: 0.0,
+                    max: MAX_LOOKAHEAD_MS,
+                },
+            )
+            .with_unit(" ms")
+            .with_step_size(0.1),
+            true_peak: BoolParam::new("True Peak", false),
+        }
+    }
+}
+
+/// A brickwall limiter with lookahead. The gain needed to keep every sample below the ceiling is
+/// held at its minimum for the duration of the lookahead window and then averaged over that same
+/// window. This makes the gain ramp down smoothly and reach its target right when the peak leaves
+/// the delay line, so nothing gets through above the ceiling. Detection is linked across all
+/// channels, and can optionally use the reconstructed inter-sample peaks. The audio is then
+/// delayed by the true-peak detector's latency as well, so the detector still looks ahead.
+#[derive(Default)]
+pub struct Limiter {
+    sample_rate: f32,
+    release_ms: f32,
+    release_weight: f32,
+    /// The lookahead time in samples.
+    lookahead: usize,
+    true_peak: bool,
+
+    channels: Vec<DelayLine>,
+    gain_minimum: SlidingMinimum,
+    gain_average: MovingAverage,
+    true_peak_detector: TruePeakDetector,
+    /// The required gain after the release has been applied, before it's averaged.
+    envelope: f32,
+    /// The gain applied to the last frame.
+    gain: f32,
+}
+
+/// A fixed capacity delay line.
+#[derive(Default)]
+struct DelayLine {
+    samples: Vec<f32>,
+    position: usize,
+}
+
+/// The minimum of the last `window` values, using a monotonic queue stored in a ring buffer.
+#[derive(Default)]
+struct SlidingMinimum {
+    /// Pairs of the time a value was added and the value. The values increase from front to back.
+    queue: Vec<(usize, f32)>,
+    front: usize,
+    len: usize,
+    time: usize,
+    window: usize,
+}
+
+/// The average of the last `window` values.
+#[derive(Default)]
+struct MovingAverage {
+    /// The recent values, as a ring buffer with room for the longest window.
+    values: Vec<f32>,
+    position: usize,
+    window: usize,
+    /// Summing in double precision keeps the running sum from drifting.
+    sum: f64,
+}
+
+impl Limiter {
+    /// Allocate the delay lines for `num_channels` channels at the maximum lookahead time. This
+    /// must be called from `initialize()`, followed by calls to
+    /// [`set_lookahead()`][Self::set_lookahead()], [`set_true_peak()`][Self::set_true_peak()], and
+    /// [`set_release()`][Self::set_release()].
+    pub fn initialize(&mut self, num_channels: usize, sample_rate: f32) {
+        self.sample_rate = sample_rate;
+        let max_lookahead = lookahead_samples(sample_rate, MAX_LOOKAHEAD_MS);
+
+        self.channels.clear();
+        self.channels.resize_with(num_channels, || DelayLine {
+            samples: vec![0.0; max_lookahead + TRUE_PEAK_LATENCY + 1],
+            position: 0,
+        });
+        self.true_peak_detector.initialize(num_channels);
+        self.gain_minimum.queue = vec![(0, 0.0); max_lookahead + 1];
+        self.gain_average.values = vec![0.0; max_lookahead.max(1)];
+
+        // The next calls to `set_lookahead()` and `set_release()` recompute everything for the
+        // new rate
+        self.lookahead = usize::MAX;
+        self.release_ms = -1.0;
+        self.reset();
+    }
+
+    pub fn reset(&mut self) {
+        for channel in &mut self.channels {
+            channel.samples.fill(0.0);
+        }
+        self.gain_minimum.reset(self.lookahead.saturating_add(1));
+        self.gain_average.reset(self.lookahead);
+        self.true_peak_detector.reset();
+        self.envelope = 1.0;
+        self.gain = 1.0;
+    }
+
+    /// Change the lookahead time. The audio in the delay lines and the recent gain values are
+    /// kept, so changing the lookahead during playback doesn't cause dropouts. Returns `true` if
+    /// the latency changed, in which case it needs to be reported to the host again.
+    pub fn set_lookahead(&mut self, lookahead_ms: f32) -> bool {
+        let lookahead = lookahead_samples(self.sample_rate, lookahead_ms.min(MAX_LOOKAHEAD_MS));
+        if lookahead == self.lookahead {
+            return false;
+        }
+
+        self.lookahead = lookahead;
+        self.gain_minimum.set_window(lookahead + 1);
+        self.gain_average.set_window(lookahead);
+
+        true
+    }
+
+    /// Enable or disable true-peak detection. The limiter's state is cleared when this changes.
+    /// Returns `true` if the latency changed.
+    pub fn set_true_peak(&mut self, true_peak: bool) -> bool {
+        if true_peak == self.true_peak {
+            return false;
+        }
+
+        self.true_peak = true_peak;
+        self.reset();
+
+        true
+    }
+
+    /// Update the release time. Nothing is recomputed when it hasn't changed.
+    pub fn set_release(&mut self, release_ms: f32) {
+        if release_ms != self.release_ms {
+            self.release_ms = release_ms;
+            self.release_weight = envelope_weight(self.sample_rate, release_ms);
+        }
+    }
+
+    /// The latency introduced by the lookahead and the true-peak detection, in samples.
+    pub fn latency(&self) -> u32 {
+        self.delay() as u32
+    }
+
+    fn delay(&self) -> usize {
+        if self.true_peak {
+            self.lookahead + TRUE_PEAK_LATENCY
+        } else {
+            self.lookahead
+        }
+    }
+
+    /// The current gain reduction as a linear gain factor.
+    pub fn gain_reduction(&self) -> f32 {
+        self.gain
+    }
+
+    /// Limit a single frame containing one sample for every channel. `ceiling` is a gain factor.
+    pub fn process(&mut self, frame: &mut [f32], ceiling: f32) {
+        let peak = if self.true_peak {
+            frame
+                .iter()
+                .enumerate()
+                .fold(0.0f32, |peak, (channel_idx, sample)| {
+                    peak.max(self.true_peak_detector.process(channel_idx, *sample))
+                })
+        } else {
+            frame
+                .iter()
+                .fold(0.0f32, |peak, sample| peak.max(sample.abs()))
+        };
+        let required_gain = if peak > ceiling { ceiling / peak } else { 1.0 };
+
+        let held_gain = self.gain_minimum.push(required_gain);
+        self.envelope = if held_gain < self.envelope {
+            held_gain
+        } else {
+            self.envelope * self.release_weight + held_gain * (1.0 - self.release_weight)
+        };
+        let gain = self.gain_average.push(self.envelope);
+        self.gain = gain;
+
+        let delay = self.delay();
+        for (channel, sample) in self.channels.iter_mut().zip(frame) {
+            let delayed = channel.process(*sample, delay);
+
+            // Rounding errors in the averaging could let through a tiny overshoot
+            *sample = (delayed * gain).clamp(-ceiling, ceiling);
+        }
+    }
+}
+
+fn lookahead_samples(sample_rate: f32, lookahead_ms: f32) -> usize {
+    (sample_rate * lookahead_ms / 1000.0).round() as usize
+}
+
+impl DelayLine {
+    /// Write a sample and read back the sample from `delay` samples ago.
+    fn process(&mut self, sample: f32, delay: usize) -> f32 {
+        let capacity = self.samples.len();
+        self.samples[self.position] = sample;
+        let delayed = self.samples[(self.position + capacity - delay) % capacity];
+        self.position = (self.position + 1) % capacity;
+
+        delayed
+    }
+}
+
+impl SlidingMinimum {
+    fn reset(&mut self, window: usize) {
+        self.front = 0;
+        self.len = 0;
+        self.time = 0;
+        self.set_window(window);
+    }
+
+    /// Change the window length. Values that fall outside of a shorter window are dropped on the
+    /// next push.
+    fn set_window(&mut self, window: usize) {
+        self.window = window.min(self.queue.len());
+    }
+
+    /// Add a value and return the minimum of the last `window` values.
+    fn push(&mut self, value: f32) -> f32 {
+        let capacity = self.queue.len();
+
+        // Expired values are removed first so the queue never holds more than `window` values
+        while self.len > 0 && self.queue[self.front].0 + self.window <= self.time {
+            self.front = (self.front + 1) % capacity;
+            self.len -= 1;
+        }
+
+        // Values that are larger than the new value can never be the minimum again
+        while self.len > 0 && self.queue[(self.front + self.len - 1) % capacity].1 >= value {
+            self.len -= 1;
+        }
+        self.queue[(self.front + self.len) % capacity] = (self.time, value);
+        self.len += 1;
+        self.time += 1;
+
+        self.queue[self.front].1
+    }
+}
+
+impl MovingAverage {
+    fn reset(&mut self, window: usize) {
+        self.values.fill(1.0);
+        self.position = 0;
+        self.set_window(window);
+    }
+
+    /// Change the window length while keeping the recent values. The sum is recomputed for the
+    /// new window.
+    fn set_window(&mut self, window: usize) {
+        let capacity = self.values.len();
+        self.window = window.min(capacity);
+        self.sum = (1..=self.window)
+            .map(|age| self.values[(self.position + capacity - age) % capacity] as f64)
+            .sum();
+    }
+
+    /// Add a value and return the average of the last `window` values. With a window of zero the
+    /// value is passed through as is.
+    fn push(&mut self, value: f32) -> f32 {
+        let capacity = self.values.len();
+        let expired = self.values[(self.position + capacity - self.window) % capacity];
+        self.values[self.position] = value;
+        self.position = (self.position + 1) % capacity;
+        if self.window == 0 {
+            return value;
+        }
+
+        self.sum += (value - expired) as f64;
+
+        (self.sum / self.window as f64) as f32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f32::consts::PI;
+
+    const SAMPLE_RATE: f32 = 48_000.0;
+    const CEILING: f32 = 0.5;
+    /// The limiter clamps its output as a last resort, so the tests check the gain it applies to
+    /// the delayed input instead. Rounding errors in the averaging are allowed to overshoot by
+    /// this much.
+    const TOLERANCE: f32 = 1e-4;
+
+    /// Bursts of a bass note well above the ceiling, with quiet parts in between.
+    fn bursts(sample_idx: usize) -> f32 {
+        let level = if sample_idx % 4800 < 300 { 3.0 } else { 0.2 };
+
+        (2.0 * PI * 100.0 * sample_idx as f32 / SAMPLE_RATE).sin() * level
+    }
+
+    fn limiter(lookahead_ms: f32) -> Limiter {
+        let mut limiter = Limiter::default();
+        limiter.initialize(1, SAMPLE_RATE);
+        limiter.set_lookahead(lookahead_ms);
+        limiter.set_true_peak(false);
+        limiter.set_release(10.0);
+
+        limiter
+    }
+
+    /// Run the bursts through `limiter`, calling `before_sample` before every sample. Returns the
+    /// loudest sample the limiter would have let through without the final clamp, and the lowest
+    /// gain at the end of a quiet part.
+    fn run(
+        limiter: &mut Limiter,
+        mut before_sample: impl FnMut(&mut Limiter, usize),
+    ) -> (f32, f32) {
+        let mut inputs = Vec::new();
+        let mut loudest = 0.0f32;
+        let mut min_recovered_gain = 1.0f32;
+        for sample_idx in 0..SAMPLE_RATE as usize {
+            before_sample(limiter, sample_idx);
+
+            let input = bursts(sample_idx);
+            inputs.push(input);
+            limiter.process(&mut [input], CEILING);
+
+            let latency = limiter.latency() as usize;
+            if sample_idx >= latency {
+                loudest =
+                    loudest.max((inputs[sample_idx - latency] * limiter.gain_reduction()).abs());
+            }
+            if sample_idx % 4800 == 4799 {
+                min_recovered_gain = min_recovered_gain.min(limiter.gain_reduction());
+            }
+        }
+
+        (loudest, min_recovered_gain)
+    }
+
+    #[test]
+    fn reports_lookahead_as_latency() {
+        assert_eq!(limiter(5.0).latency(), 240);
+        assert_eq!(limiter(0.0).latency(), 0);
+
+        let mut limiter = limiter(5.0);
+        assert!(limiter.set_true_peak(true));
+        assert_eq!(limiter.latency(), 240 + TRUE_PEAK_LATENCY as u32);
+    }
+
+    #[test]
+    fn stays_under_ceiling() {
+        for lookahead_ms in [0.0, 1.0, 5.0, MAX_LOOKAHEAD_MS] {
+            let (loudest, min_recovered_gain) = run(&mut limiter(lookahead_ms), |_, _| ());
+            assert!(
+                loudest <= CEILING * (1.0 + TOLERANCE),
+                "{lookahead_ms} ms lookahead: {loudest}"
+            );
+            assert!(
+                min_recovered_gain > 0.99,
+                "{lookahead_ms} ms lookahead: {min_recovered_gain}"
+            );
+        }
+    }
+
+    #[test]
+    fn stays_under_ceiling_while_lookahead_changes() {
+        let (loudest, min_recovered_gain) = run(&mut limiter(5.0), |limiter, sample_idx| {
+            if sample_idx % 64 == 0 {
+                limiter.set_lookahead(5.0 + 4.0 * (sample_idx as f32 / 9600.0).sin());
+            }
+        });
+        assert!(loudest <= CEILING * (1.0 + TOLERANCE), "{loudest}");
+        assert!(min_recovered_gain > 0.99, "{min_recovered_gain}");
+    }
+}
@@ -0,0 +1,280 @@
+use nih_plug::prelude::*;
+use std::f32::consts::PI;
+
+/// The number of 2x stages needed for the highest oversampling factor.
+pub const MAX_OVERSAMPLING_STAGES: usize = 3;
+
+/// The index of the center tap of the half-band filter. This needs to be even for the latency to
+/// work out to a whole number of samples at every factor.
+const HALFBAND_CENTER: usize = 16;
+/// The non-zero taps of a half-band filter are the odd ones, plus the center tap.
+const POLYPHASE_LENGTH: usize = HALFBAND_CENTER;
+/// The Kaiser window's beta, which gives roughly 80 dB of stopband attenuation.
+const KAISER_BETA: f32 = 8.0;
+
+/// The oversampling factor used for the nonlinear stages. Higher factors alias less, at the cost
+/// of more CPU and more latency.
+#[derive(Enum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OversamplingFactor {
+    #[name = "1x"]
+    X1,
+    #[name = "2x"]
+    X2,
+    #[name = "4x"]
+    X4,
+    #[name = "8x"]
+    X8,
+}
+
+impl OversamplingFactor {
+    /// The number of cascaded 2x stages.
+    pub fn num_stages(self) -> usize {
+        match self {
+            OversamplingFactor::X1 => 0,
+            OversamplingFactor::X2 => 1,
+            OversamplingFactor::X4 => 2,
+            OversamplingFactor::X8 => 3,
+        }
+    }
+}
+
+/// Runs a block of audio at up to eight times the sample rate. Every 2x stage upsamples with a
+/// polyphase half-band FIR filter, and the matching downsampler filters with the same kernel
+/// before dropping every other sample. Because all even taps of a half-band filter except the
+/// center are zero, each stage only needs half of the multiplies.
+pub struct Oversampler {
+    num_stages: usize,
+    /// The odd taps of the half-band kernel, shared by all stages.
+    odd_taps: [f32; POLYPHASE_LENGTH],
+
+    channels: Vec<ChannelState>,
+}
+
+struct ChannelState {
+    upsamplers: [Upsampler2x; MAX_OVERSAMPLING_STAGES],
+    downsamplers: [Downsampler2x; MAX_OVERSAMPLING_STAGES],
+    /// The output of every upsampling stage. `buffers[n]` holds `max_block_size * 2^(n + 1)`
+    /// samples.
+    buffers: [Vec<f32>; MAX_OVERSAMPLING_STAGES],
+}
+
+#[derive(Default, Clone, Copy)]
+struct Upsampler2x {
+    history: History<POLYPHASE_LENGTH>,
+}
+
+#[derive(Default, Clone, Copy)]
+struct Downsampler2x {
+    even_history: History<POLYPHASE_LENGTH>,
+    odd_history: History<POLYPHASE_LENGTH>,
+}
+
+/// A ring buffer holding the last `N` samples. The samples are stored twice so they can always be
+/// read back as a single contiguous slice.
+#[derive(Clone, Copy)]
+pub(super) struct History<const N: usize> {
+    samples: [[f32; N]; 2],
+    position: usize,
+}
+
+impl<const N: usize> Default for History<N> {
+    fn default() -> Self {
+        Self {
+            samples: [[0.0; N]; 2],
+            position: 0,
+        }
+    }
+}
+
+impl<const N: usize> History<N> {
+    pub(super) fn push(&mut self, sample: f32) {
+        self.position = (self.position + N - 1) % N;
+        self.samples[0][self.position] = sample;
+        self.samples[1][self.position] = sample;
+    }
+
+    /// The stored samples, with the most recent sample first.
+    pub(super) fn as_slice(&self) -> &[f32] {
+        &self.samples.as_flattened()[self.position..self.position + N]
+    }
+}
+
+impl Default for Oversampler {
+    fn default() -> Self {
+        Self {
+            num_stages: 0,
+            odd_taps: halfband_odd_taps(),
+
+            channels: Vec::new(),
+        }
+    }
+}
+
+impl Oversampler {
+    /// Allocate the state and the buffers for `num_channels` channels. The buffers are sized for
+    /// the highest factor so the factor can be changed while processing without allocating. This
+    /// must be called from `initialize()`.
+    pub fn initialize(&mut self, num_channels: usize, max_block_size: usize) {
+        self.channels.clear();
+        self.channels.resize_with(num_channels, || ChannelState {
+            upsamplers: Default::default(),
+            downsamplers: Default::default(),
+            buffers: std::array::from_fn(|stage_idx| vec![0.0; max_block_size << (stage_idx + 1)]),
+        });
+    }
+
+    pub fn reset(&mut self) {
+        for channel in &mut self.channels {
+            channel.upsamplers = Default::default();
+            channel.downsamplers = Default::default();
+        }
+    }
+
+    /// Change the oversampling factor. The filter state is cleared when the factor changes.
+    /// Returns `true` if the factor changed, in which case the latency needs to be reported to the
+    /// host again.
+    pub fn set_factor(&mut self, factor: OversamplingFactor) -> bool {
+        let num_stages = factor.num_stages();
+        if num_stages == self.num_stages {
+            return false;
+        }
+
+        self.num_stages = num_stages;
+        self.reset();
+
+        true
+    }
+
+    /// The number of 2x stages for the current factor. The oversampled block for an input block
+    /// of `n` samples contains `n << num_stages()` samples.
+    pub fn num_stages(&self) -> usize {
+        self.num_stages
+    }
+
+    /// The latency introduced by the up- and downsampling filters, in samples at the original
+    /// sample rate. Each stage delays the signal by the filter's center tap twice, once while
+    /// upsampling and once while downsampling, at that stage's higher sample rate.
+    pub fn latency(&self) -> u32 {
+        (1..=self.num_stages)
+            .map(|stage| ((HALFBAND_CENTER * 2) >> stage) as u32)
+            .sum()
+    }
+
+    /// Upsample `block`, run `f` on the oversampled version, and write the downsampled result
+    /// back to `block`. `block` may not be longer than the `max_block_size` passed to
+    /// [`initialize()`][Self::initialize()].
+    pub fn process(&mut self, channel_idx: usize, block: &mut [f32], f: impl FnOnce(&mut [f32])) {
+        if self.num_stages == 0 {
+            f(block);
+            return;
+        }
+
+        let channel = &mut self.channels[channel_idx];
+        let num_stages = self.num_stages;
+        let block_len = block.len();
+        nih_debug_assert!(block_len << num_stages <= channel.buffers[num_stages - 1].len());
+
+        channel.upsamplers[0].process(&self.odd_taps, block, &mut channel.buffers[0]);
+        for stage_idx in 1..num_stages {
+            let (lower, higher) = channel.buffers.split_at_mut(stage_idx);
+            channel.upsamplers[stage_idx].process(
+                &self.odd_taps,
+                &lower[stage_idx - 1][..block_len << stage_idx],
+                &mut higher[0],
+            );
+        }
+
+        f(&mut channel.buffers[num_stages - 1][..block_len << num_stages]);
+
+        for stage_idx in (1..num_stages).rev() {
+            let (lower, higher) = channel.buffers.split_at_mut(stage_idx);
+            channel.downsamplers[stage_idx].process(
+                &self.odd_taps,
+                &higher[0][..block_len << (stage_idx + 1)],
+                &mut lower[stage_idx - 1],
+            );
+        }
+        channel.downsamplers[0].process(
+            &self.odd_taps,
+            &channel.buffers[0][..block_len << 1],
+            block,
+        );
+    }
+}
+
+impl Upsampler2x {
+    /// Upsample `input` into the first `input.len() * 2` samples of `output`.
+    fn process(&mut self, odd_taps: &[f32; POLYPHASE_LENGTH], input: &[f32], output: &mut [f32]) {
+        for (sample, output) in input.iter().zip(output.chunks_exact_mut(2)) {
+            self.history.push(*sample);
+            let history = self.history.as_slice();
+
+            // The kernel is doubled to make up for the zeroes that would have been stuffed in
+            // between the input samples. The even phase only has the center tap, which is 0.5.
+            output[0] = history[HALFBAND_CENTER / 2];
+            output[1] = 2.0 * dot(odd_taps, history);
+        }
+    }
+}
+
+impl Downsampler2x {
+    /// Filter and decimate `input` into the first `input.len() / 2` samples of `output`.
+    fn process(&mut self, odd_taps: &[f32; POLYPHASE_LENGTH], input: &[f32], output: &mut [f32]) {
+        for (input, output) in input.chunks_exact(2).zip(output.iter_mut()) {
+            // The odd history needs to lag behind by one sample, so it's updated after filtering
+            self.even_history.push(input[0]);
+            *output = 0.5 * self.even_history.as_slice()[HALFBAND_CENTER / 2]
+                + dot(odd_taps, self.odd_history.as_slice());
+            self.odd_history.push(input[1]);
+        }
+    }
+}
+
+fn dot(taps: &[f32], samples: &[f32]) -> f32 {
+    taps.iter()
+        .zip(samples)
+        .map(|(tap, sample)| tap * sample)
+        .sum()
+}
+
+/// The odd taps of a Kaiser windowed sinc low-pass filter with its cutoff at a quarter of the
+/// sample rate. The even taps of such a filter are zero except for the center tap, which is 0.5.
+/// The odd taps are normalized to sum to 0.5 so both polyphase branches have unity gain at DC.
+fn halfband_odd_taps() -> [f32; POLYPHASE_LENGTH] {
+    let mut taps = [0.0; POLYPHASE_LENGTH];
+    for (tap_idx, tap) in taps.iter_mut().enumerate() {
+        let offset = (tap_idx * 2 + 1) as f32 - HALFBAND_CENTER as f32;
+        let sinc = (PI * offset / 2.0).sin() / (PI * offset / 2.0);
+        let window = kaiser_window(offset / HALFBAND_CENTER as f32, KAISER_BETA);
+
+        *tap = 0.5 * sinc * window;
+    }
+
+    let sum: f32 = taps.iter().sum();
+    taps.iter_mut().for_each(|tap| *tap *= 0.5 / sum);
+
+    taps
+}
+
+/// The Kaiser window at `relative_position`, which runs from -1 at the start of the window to 1
+/// at its end.
+pub(super) fn kaiser_window(relative_position: f32, beta: f32) -> f32 {
+    let x = (1.0 - relative_position * relative_position).max(0.0).sqrt();
+
+    bessel_i0(beta * x) / bessel_i0(beta)
+}
+
+/// The zeroth order modified Bessel function of the first kind, for the Kaiser window.
+fn bessel_i0(x: f32) -> f32 {
+    let mut result = 1.0;
+    let mut term = 1.0;
+    for k in 1..32 {
+        term *= (x / (2.0 * k as f32)).powi(2);
+        result += term;
+        if term < result * 1e-9 {
+            break;
+        }
+    }
+
+    result
+}
@@ -0,0 +1,199 @@
+use nih_plug::prelude::*;
+
+use super::biquad::{Biquad, BiquadCoefficients, BUTTERWORTH_Q};
+
+/// The cutoff frequency of the high-pass filter that removes the DC offset introduced by the bias
+/// and by the asymmetric curves.
+const DC_BLOCKER_FREQUENCY: f32 = 10.0;
+
+#[derive(Params)]
+pub struct SaturationParams {
+    #[id = "sat_curve"]
+    pub curve: EnumParam<SaturationCurve>,
+    #[id = "sat_drive"]
+    pub drive: FloatParam,
+    /// A DC offset added before the curve, which makes the saturation asymmetric and adds even
+    /// harmonics.
+    #[id = "sat_bias"]
+    pub bias: FloatParam,
+    #[id = "sat_trim"]
+    pub output_trim: FloatParam,
+    /// The balance between the dry and the saturated signal. This is fully dry by default, so the
+    /// stage doesn't color the sound until it's turned up.
+    #[id = "sat_mix"]
+    pub mix: FloatParam,
+}
+
+/// The transfer curves for the saturation stage.
+#[derive(Enum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SaturationCurve {
+    #[name = "Soft Clip"]
+    Tanh,
+    #[name = "Hard Clip"]
+    HardClip,
+    #[name = "Tube"]
+    Tube,
+    #[name = "Foldback"]
+    Foldback,
+    #[name = "Cubic"]
+    Cubic,
+}
+
+impl Default for SaturationParams {
+    fn default() -> Self {
+        Self {
+            curve: EnumParam::new("Curve", SaturationCurve::Tanh),
+            drive: FloatParam::new(
+                "Drive",
+                util::db_to_gain(0.0),
+                FloatRange::Skewed {
+                    min: util::db_to_gain(0.0),
+                    max: util::db_to_gain(36.0),
+                    factor: FloatRange::gain_skew_factor(0.0, 36.0),
+                },
+            )
+            .with_smoother(SmoothingStyle::Logarithmic(50.0))
+            .with_unit(" dB")
+            .with_value_to_string(formatters::v2s_f32_gain_to_db(1))
+            .with_string_to_value(formatters::s2v_f32_gain_to_db()),
+            bias: FloatParam::new(
+                "Bias",
+                0.0,
+                FloatRange::Linear {
+                    min: -0.5,
+                    max: 0.5,
+                },
+            )
+            .with_smoother(SmoothingStyle::Linear(50.0))
+            .with_value_to_string(formatters::v2s_f32_rounded(2)),
+            output_trim: FloatParam::new(
+                "Output Trim",
+                util::db_to_gain(0.0),
+                FloatRange::Skewed {
+                    min: util::db_to_gain(-24.0),
+                    max: util::db_to_gain(12.0),
+                    factor: FloatRange::gain_skew_factor(-24.0, 12.0),
+                },
+            )
+            .with_smoother(SmoothingStyle::Logarithmic(50.0))
+            .with_unit(" dB")
+            .with_value_to_string(formatters::v2s_f32_gain_to_db(1))
+            .with_string_to_value(formatters::s2v_f32_gain_to_db()),
+            mix: FloatParam::new(
+                "Saturation Mix",
+                0.0,
+                FloatRange::Linear { min: 0.0, max: 1.0 },
+            )
+            .with_smoother(SmoothingStyle::Linear(20.0))
+            .with_unit("%")
+            .with_value_to_string(formatters::v2s_f32_percentage(0))
+            .with_string_to_value(formatters::s2v_f32_percentage()),
+        }
+    }
+}
+
+impl SaturationCurve {
+    /// Apply the transfer curve to a single sample. Every curve has a slope of one around zero, so
+    /// the curves only differ in how they saturate and the drive alone sets the small-signal gain.
+    pub fn apply(self, x: f32) -> f32 {
+        match self {
+            SaturationCurve::Tanh => x.tanh(),
+            SaturationCurve::HardClip => x.clamp(-1.0, 1.0),
+            SaturationCurve::Tube => {
+                // The positive half clips softly while the negative half is allowed to go further
+                // before it compresses, like a single ended triode stage
+                if x >= 0.0 {
+                    x.tanh()
+                } else {
+                    (x * 0.5).tanh() * 2.0
+                }
+            }
+            SaturationCurve::Foldback => {
+                // Everything that exceeds the [-1, 1] range is mirrored back into it
+                let wrapped = (x - 1.0).rem_euclid(4.0);
+                (wrapped - 2.0).abs() - 1.0
+            }
+            SaturationCurve::Cubic => {
+                // The input is scaled down so the curve reaches its peak at 1.5 instead of at 1
+                let x = (x / 1.5).clamp(-1.0, 1.0);
+                1.5 * x - 0.5 * x * x * x
+            }
+        }
+    }
+}
+
+/// The saturation stage's amounts for a single sample. These are read from the smoothed parameter
+/// values. `drive` and `output_trim` are gain factors, and `mix` is the amount of the saturated
+/// signal in the output.
+#[derive(Debug, Clone, Copy)]
+pub struct SaturationAmounts {
+    pub drive: f32,
+    pub bias: f32,
+    pub output_trim: f32,
+    pub mix: f32,
+}
+
+/// A waveshaper with selectable transfer curves. The input is multiplied by the drive, offset by
+/// the bias, shaped, and scaled back down by the square root of the drive so the drive mostly
+/// changes the character rather than the level. Quiet signals still come out louder by that square
+/// root. The result is mixed with the dry signal and high-passed to remove the DC offset, so both
+/// halves of the mix get the same phase response.
+#[derive(Default)]
+pub struct Saturator {
+    channels: Vec<ChannelState>,
+}
+
+#[derive(Default)]
+struct ChannelState {
+    dc_blocker: Biquad,
+}
+
+impl Saturator {
+    /// Allocate state for `num_channels` channels. This must be called from `initialize()`,
+    /// followed by a call to [`set_sample_rate()`][Self::set_sample_rate()].
+    pub fn initialize(&mut self, num_channels: usize) {
+        self.channels
+            .resize_with(num_channels, ChannelState::default);
+    }
+
+    /// Set the rate the saturator runs at. When it runs inside of the oversampler this is the
+    /// oversampled rate. This also clears the filter state.
+    pub fn set_sample_rate(&mut self, sample_rate: f32) {
+        let dc_blocker =
+            BiquadCoefficients::highpass(sample_rate, DC_BLOCKER_FREQUENCY, BUTTERWORTH_Q);
+        for channel in &mut self.channels {
+            channel.dc_blocker.coefficients = dc_blocker;
+        }
+
+        self.reset();
+    }
+
+    pub fn reset(&mut self) {
+        for channel in &mut self.channels {
+            channel.dc_blocker.reset();
+        }
+    }
+
+    /// Saturate a single sample for a channel.
+    pub fn process(
+        &mut self,
+        channel_idx: usize,
+        sample: f32,
+        curve: SaturationCurve,
+        amounts: SaturationAmounts,
+    ) -> f32 {
+        let channel = &mut self.channels[channel_idx];
+        let SaturationAmounts {
+            drive,
+            bias,
+            output_trim,
+            mix,
+        } = amounts;
+
+        // The bias is removed again after shaping so silence stays silent
+        let shaped = curve.apply(sample * drive + bias) - curve.apply(bias);
+        let shaped = shaped / drive.sqrt() * output_trim;
+
+        channel.dc_blocker.process(sample + (shaped - sample) * mix)
+    }
+}
@@ -0,0 +1,136 @@
+use nih_plug::prelude::*;
+
+use super::crossover::{Crossover, CrossoverSlope};
+
+#[derive(Params)]
+pub struct StereoParams {
+    /// Which part of a mid/side encoded signal runs through the processing chain. This is always
+    /// off for mono audio.
+    #[id = "mid_side"]
+    pub mid_side: EnumParam<MidSideMode>,
+    /// The level of the side signal, where 0% is mono and 200% doubles the stereo width.
+    #[id = "width"]
+    pub width: FloatParam,
+
+    /// Sums everything below `mono_frequency` to mono.
+    #[id = "mono_bass"]
+    pub mono_bass: BoolParam,
+    #[id = "mono_freq"]
+    pub mono_frequency: FloatParam,
+}
+
+/// Selects the channels that get processed. With anything but [`MidSideMode::Off`] the first
+/// channel carries the mid signal and the second channel the side signal during processing.
+#[derive(Enum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MidSideMode {
+    #[name = "Left/Right"]
+    Off,
+    #[name = "Mid Only"]
+    Mid,
+    #[name = "Side Only"]
+    Side,
+    #[name = "Mid and Side"]
+    Both,
+}
+
+impl MidSideMode {
+    /// The channel that skips the processing in the mid only and side only modes.
+    pub fn bypassed_channel(self) -> Option<usize> {
+        match self {
+            MidSideMode::Mid => Some(1),
+            MidSideMode::Side => Some(0),
+            MidSideMode::Off | MidSideMode::Both => None,
+        }
+    }
+}
+
+impl Default for StereoParams {
+    fn default() -> Self {
+        Self {
+            mid_side: EnumParam::new("Mid/Side", MidSideMode::Off),
+            width: FloatParam::new("Width", 1.0, FloatRange::Linear { min: 0.0, max: 2.0 })
+                .with_smoother(SmoothingStyle::Linear(20.0))
+                .with_unit("%")
+                .with_value_to_string(formatters::v2s_f32_percentage(0))
+                .with_string_to_value(formatters::s2v_f32_percentage()),
+
+            mono_bass: BoolParam::new("Mono Bass", false),
+            mono_frequency: FloatParam::new(
+                "Mono Below",
+                120.0,
+                FloatRange::Skewed {
+                    min: 20.0,
+                    max: 500.0,
+                    factor: FloatRange::skew_factor(-1.0),
+                },
+            )
+            .with_smoother(SmoothingStyle::Logarithmic(50.0))
+            .with_value_to_string(formatters::v2s_f32_hz_then_khz(0))
+            .with_string_to_value(formatters::s2v_f32_hz_then_khz()),
+        }
+    }
+}
+
+/// Convert a left/right pair to mid/side. The mid signal is the average of both channels, so a
+/// mono signal keeps its level.
+pub fn encode_mid_side(left: f32, right: f32) -> (f32, f32) {
+    ((left + right) * 0.5, (left - right) * 0.5)
+}
+
+/// Convert a mid/side pair back to left/right, with the side signal scaled by `width`.
+pub fn decode_mid_side(mid: f32, side: f32, width: f32) -> (f32, f32) {
+    (mid + side * width, mid - side * width)
+}
+
+/// Sums the low end to mono while leaving the highs untouched. Every channel is split with the
+/// same 24 dB/octave Linkwitz-Riley crossover, so all channels get an identical all-pass phase
+/// response and the stereo image above the split is preserved.
+#[derive(Default)]
+pub struct BassMonoizer {
+    enabled: bool,
+    crossover: Crossover,
+}
+
+impl BassMonoizer {
+    /// Allocate state for `num_channels` channels. This must be called from `initialize()`,
+    /// followed by a call to [`configure()`][Self::configure()].
+    pub fn initialize(&mut self, num_channels: usize, sample_rate: f32) {
+        self.crossover.initialize(num_channels, sample_rate);
+    }
+
+    pub fn reset(&mut self) {
+        self.crossover.reset();
+    }
+
+    /// Enable or disable the mono-izer and set the split frequency. The filter state is cleared
+    /// when the mono-izer gets enabled so it doesn't start with stale samples.
+    pub fn configure(&mut self, enabled: bool, frequency: f32) {
+        if enabled && !self.enabled {
+            self.reset();
+        }
+        self.enabled = enabled;
+
+        // Only the first split is used with two bands, the other frequencies are ignored
+        self.crossover
+            .configure(2, CrossoverSlope::Lr24, [frequency, 0.0, 0.0]);
+    }
+
+    /// Process a single frame containing one sample for every channel. Mono input is left alone.
+    pub fn process(&mut self, frame: &mut [f32]) {
+        if !self.enabled || frame.len() < 2 {
+            return;
+        }
+
+        let mut low_sum = 0.0;
+        for (channel_idx, sample) in frame.iter_mut().enumerate() {
+            let [low, high, ..] = self.crossover.split(channel_idx, *sample);
+            low_sum += low;
+            *sample = high;
+        }
+
+        let low_mono = low_sum / frame.len() as f32;
+        for sample in frame {
+            *sample += low_mono;
+        }
+    }
+}
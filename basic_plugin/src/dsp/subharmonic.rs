@@ -0,0 +1,165 @@
+use nih_plug::prelude::Enum;
+
+use super::biquad::{Biquad, BiquadCoefficients, BUTTERWORTH_Q};
+use super::envelope_weight;
+
+/// The Q of the band-pass filter the pitch tracker listens through. Low enough to follow a bass
+/// line across the band, high enough to keep the harmonics from causing extra zero crossings.
+const BAND_SELECT_Q: f32 = 1.0;
+
+const ENVELOPE_ATTACK_MS: f32 = 5.0;
+const ENVELOPE_RELEASE_MS: f32 = 60.0;
+/// Zero crossings only count once the band-passed signal crosses this fraction of its envelope,
+/// so noise around zero can't flip the dividers.
+const HYSTERESIS: f32 = 0.1;
+
+/// Which octaves the sub-harmonic synthesizer generates.
+#[derive(Enum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SubOctave {
+    #[name = "-1 Octave"]
+    One,
+    #[name = "-2 Octaves"]
+    Two,
+    #[name = "-1 & -2 Octaves"]
+    Both,
+}
+
+/// An octave divider in the spirit of the classic analog octave pedals. The input band is tracked
+/// with a hysteresis zero crossing detector that clocks two flip-flops, giving square waves at
+/// half and a quarter of the input frequency. These follow the input's envelope and are low-pass
+/// filtered into a round sub signal.
+#[derive(Default)]
+pub struct SubHarmonicSynth {
+    sample_rate: f32,
+    band_frequency: f32,
+    tone_frequency: f32,
+
+    envelope_attack_weight: f32,
+    envelope_release_weight: f32,
+
+    channels: Vec<ChannelState>,
+}
+
+#[derive(Default)]
+struct ChannelState {
+    band_select: Biquad,
+    envelope: f32,
+    /// Whether the band-passed signal last crossed the upper or the lower hysteresis threshold.
+    positive: bool,
+    /// Toggles on every period of the input, so it runs one octave down.
+    divider_1: bool,
+    /// Toggles on every period of `divider_1`, so it runs two octaves down.
+    divider_2: bool,
+    /// Two cascaded Butterworth low-pass filters, for a 24 dB/octave slope.
+    tone: [Biquad; 2],
+}
+
+impl SubOctave {
+    /// The levels for the one and two octaves down square waves.
+    fn levels(self) -> (f32, f32) {
+        match self {
+            SubOctave::One => (1.0, 0.0),
+            SubOctave::Two => (0.0, 1.0),
+            SubOctave::Both => (0.5, 0.5),
+        }
+    }
+}
+
+impl SubHarmonicSynth {
+    /// Allocate state for `num_channels` channels. This must be called from `initialize()`,
+    /// followed by a call to [`set_frequencies()`][Self::set_frequencies()].
+    pub fn initialize(&mut self, num_channels: usize, sample_rate: f32) {
+        self.sample_rate = sample_rate;
+        self.envelope_attack_weight = envelope_weight(sample_rate, ENVELOPE_ATTACK_MS);
+        self.envelope_release_weight = envelope_weight(sample_rate, ENVELOPE_RELEASE_MS);
+        self.channels
+            .resize_with(num_channels, ChannelState::default);
+
+        // The next call to `set_frequencies()` recomputes the filters for the new rate
+        self.band_frequency = 0.0;
+        self.tone_frequency = 0.0;
+    }
+
+    pub fn reset(&mut self) {
+        for channel in &mut self.channels {
+            channel.band_select.reset();
+            channel.envelope = 0.0;
+            channel.positive = false;
+            channel.divider_1 = false;
+            channel.divider_2 = false;
+            channel.tone.iter_mut().for_each(Biquad::reset);
+        }
+    }
+
+    /// Update the center frequency of the band the synth tracks and the cutoff frequency of the
+    /// tone filter. Filters are only recomputed when their frequency changes.
+    pub fn set_frequencies(&mut self, band_frequency: f32, tone_frequency: f32) {
+        if band_frequency != self.band_frequency {
+            self.band_frequency = band_frequency;
+
+            let coefficients =
+                BiquadCoefficients::bandpass(self.sample_rate, band_frequency, BAND_SELECT_Q);
+            for channel in &mut self.channels {
+                channel.band_select.coefficients = coefficients;
+            }
+        }
+
+        if tone_frequency != self.tone_frequency {
+            self.tone_frequency = tone_frequency;
+
+            let coefficients =
+                BiquadCoefficients::lowpass(self.sample_rate, tone_frequency, BUTTERWORTH_Q);
+            for channel in &mut self.channels {
+                for filter in &mut channel.tone {
+                    filter.coefficients = coefficients;
+                }
+            }
+        }
+    }
+
+    /// Track the input sample for a channel and return the synthesized sub signal, scaled by
+    /// `level`. The result still needs to be added to the signal.
+    pub fn process(
+        &mut self,
+        channel_idx: usize,
+        sample: f32,
+        octave: SubOctave,
+        level: f32,
+    ) -> f32 {
+        let channel = &mut self.channels[channel_idx];
+
+        let band = channel.band_select.process(sample);
+        let rectified = band.abs();
+        let envelope_weight = if rectified > channel.envelope {
+            self.envelope_attack_weight
+        } else {
+            self.envelope_release_weight
+        };
+        channel.envelope = channel.envelope * envelope_weight + rectified * (1.0 - envelope_weight);
+
+        let threshold = channel.envelope * HYSTERESIS;
+        if !channel.positive && band > threshold {
+            channel.positive = true;
+
+            // Every rising edge is one period of the input
+            channel.divider_1 = !channel.divider_1;
+            if channel.divider_1 {
+                channel.divider_2 = !channel.divider_2;
+            }
+        } else if channel.positive && band < -threshold {
+            channel.positive = false;
+        }
+
+        let (level_1, level_2) = octave.levels();
+        let square_1 = if channel.divider_1 { 1.0 } else { -1.0 };
+        let square_2 = if channel.divider_2 { 1.0 } else { -1.0 };
+        let sub = (square_1 * level_1 + square_2 * level_2) * channel.envelope;
+
+        let sub = channel
+            .tone
+            .iter_mut()
+            .fold(sub, |sample, filter| filter.process(sample));
+
+        sub * level
+    }
+}
@@ -0,0 +1,152 @@
+use nih_plug::prelude::*;
+
+use super::envelope_weight;
+
+/// The envelope difference in decibels at which the full attack or sustain amount is applied.
+const DETECTION_RANGE_DB: f32 = 12.0;
+
+/// The followers run on a short RMS average of the sidechain, so the ripple of a steady bass note
+/// doesn't register as a stream of transients.
+const DETECTOR_RMS_MS: f32 = 5.0;
+
+/// The envelope follower that tracks the signal closely.
+const FAST_ATTACK_MS: f32 = 1.0;
+const FAST_RELEASE_MS: f32 = 100.0;
+/// Lags behind the fast follower at the start of a note, which marks the transient.
+const SLOW_ATTACK_MS: f32 = 30.0;
+/// Lags behind the fast follower when a note decays, which marks the sustain.
+const SLOW_RELEASE_MS: f32 = 500.0;
+
+#[derive(Params)]
+pub struct TransientParams {
+    /// Boosts or cuts the start of every note.
+    #[id = "tr_attack"]
+    pub attack: FloatParam,
+    /// Boosts or cuts the tail of every note.
+    #[id = "tr_sustain"]
+    pub sustain: FloatParam,
+    #[id = "tr_band"]
+    pub band: EnumParam<TransientBand>,
+}
+
+/// The part of the signal the transient shaper works on.
+#[derive(Enum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransientBand {
+    #[name = "Full Band"]
+    Full,
+    /// The lowest band from the band split.
+    #[name = "Low Band"]
+    Low,
+}
+
+impl Default for TransientParams {
+    fn default() -> Self {
+        Self {
+            attack: transient_amount_param("Transient Attack"),
+            sustain: transient_amount_param("Transient Sustain"),
+            band: EnumParam::new("Transient Band", TransientBand::Full),
+        }
+    }
+}
+
+fn transient_amount_param(name: &str) -> FloatParam {
+    FloatParam::new(
+        name,
+        0.0,
+        FloatRange::Linear {
+            min: -12.0,
+            max: 12.0,
+        },
+    )
+    .with_smoother(SmoothingStyle::Linear(20.0))
+    .with_unit(" dB")
+    .with_step_size(0.1)
+}
+
+/// A transient designer driven by two pairs of envelope followers, which track a short RMS average
+/// of the sidechain. A follower with a slow attack lags behind a fast one at the start of a note,
+/// and a follower with a slow release lags behind it while the note decays. The differences between
+/// them scale the attack and sustain amounts.
+/// Like the compressor, detection runs on a single sidechain value so the channels stay linked.
+#[derive(Default)]
+pub struct TransientShaper {
+    detector_weight: f32,
+    fast_attack_weight: f32,
+    fast_release_weight: f32,
+    slow_attack_weight: f32,
+    slow_release_weight: f32,
+
+    /// The sidechain's smoothed mean square.
+    detector_mean_square: f32,
+    fast_envelope: f32,
+    slow_attack_envelope: f32,
+    slow_release_envelope: f32,
+}
+
+impl TransientShaper {
+    /// Prepare the envelope followers for a new sample rate. This must be called from
+    /// `initialize()`.
+    pub fn initialize(&mut self, sample_rate: f32) {
+        self.detector_weight = envelope_weight(sample_rate, DETECTOR_RMS_MS);
+        self.fast_attack_weight = envelope_weight(sample_rate, FAST_ATTACK_MS);
+        self.fast_release_weight = envelope_weight(sample_rate, FAST_RELEASE_MS);
+        self.slow_attack_weight = envelope_weight(sample_rate, SLOW_ATTACK_MS);
+        self.slow_release_weight = envelope_weight(sample_rate, SLOW_RELEASE_MS);
+
+        self.reset();
+    }
+
+    pub fn reset(&mut self) {
+        self.detector_mean_square = 0.0;
+        self.fast_envelope = 0.0;
+        self.slow_attack_envelope = 0.0;
+        self.slow_release_envelope = 0.0;
+    }
+
+    /// Compute the gain for the next sample from the sidechain's peak value. `attack_db` and
+    /// `sustain_db` are the gains applied at the height of a transient and during a note's tail.
+    pub fn next_gain(&mut self, sidechain_peak: f32, attack_db: f32, sustain_db: f32) -> f32 {
+        self.detector_mean_square = self.detector_mean_square * self.detector_weight
+            + sidechain_peak * sidechain_peak * (1.0 - self.detector_weight);
+        let level = self.detector_mean_square.sqrt();
+
+        self.fast_envelope = follow(
+            self.fast_envelope,
+            level,
+            self.fast_attack_weight,
+            self.fast_release_weight,
+        );
+        self.slow_attack_envelope = follow(
+            self.slow_attack_envelope,
+            level,
+            self.slow_attack_weight,
+            self.fast_release_weight,
+        );
+        self.slow_release_envelope = follow(
+            self.slow_release_envelope,
+            level,
+            self.fast_attack_weight,
+            self.slow_release_weight,
+        );
+
+        let fast_db = util::gain_to_db(self.fast_envelope);
+        let transient = (fast_db - util::gain_to_db(self.slow_attack_envelope)).max(0.0);
+        let tail = (util::gain_to_db(self.slow_release_envelope) - fast_db).max(0.0);
+
+        util::db_to_gain(
+            attack_db * (transient / DETECTION_RANGE_DB).min(1.0)
+                + sustain_db * (tail / DETECTION_RANGE_DB).min(1.0),
+        )
+    }
+}
+
+/// A single step of a peak envelope follower with separate attack and release weights.
+fn follow(envelope: f32, input: f32, attack_weight: f32, release_weight: f32) -> f32 {
+    let weight = if input > envelope {
+        attack_weight
+    } else {
+        release_weight
+    };
+
+    envelope * weight + input * (1.0 - weight)
+}
@@ -0,0 +1,131 @@
+use std::f32::consts::PI;
+
+use super::oversampling::{kaiser_window, History};
+
+/// The detector reconstructs the signal at four times the sample rate, as described in ITU-R
+/// BS.1770-4 Annex 2.
+const OVERSAMPLING_FACTOR: usize = 4;
+const TAPS_PER_PHASE: usize = 12;
+const KAISER_BETA: f32 = 5.0;
+
+/// The delay between a sample entering the detector and the interpolated values around it being
+/// included in the detector's output, in samples.
+pub const TRUE_PEAK_LATENCY: usize = TAPS_PER_PHASE / 2;
+
+/// Estimates the inter-sample peaks of a signal by interpolating it with a polyphase FIR filter.
+/// A sample-peak meter misses overs that only appear once the signal is converted back to analog,
+/// which is what streaming services check for.
+pub struct TruePeakDetector {
+    /// `phases[phase][tap]` is the interpolation kernel for the `phase`th intermediate position.
+    phases: [[f32; TAPS_PER_PHASE]; OVERSAMPLING_FACTOR],
+
+    /// The last [`TAPS_PER_PHASE`] samples for every channel.
+    channels: Vec<History<TAPS_PER_PHASE>>,
+}
+
+impl Default for TruePeakDetector {
+    fn default() -> Self {
+        Self {
+            phases: interpolation_phases(),
+
+            channels: Vec::new(),
+        }
+    }
+}
+
+impl TruePeakDetector {
+    /// Allocate state for `num_channels` channels. This must be called from `initialize()`.
+    pub fn initialize(&mut self, num_channels: usize) {
+        self.channels.clear();
+        self.channels.resize(num_channels, History::default());
+    }
+
+    pub fn reset(&mut self) {
+        self.channels.fill(History::default());
+    }
+
+    /// Add a sample for a channel and return the largest absolute value of the reconstructed
+    /// signal between the last two samples. The result lags [`TRUE_PEAK_LATENCY`] samples behind
+    /// the input.
+    pub fn process(&mut self, channel_idx: usize, sample: f32) -> f32 {
+        let history = &mut self.channels[channel_idx];
+        history.push(sample);
+        let samples = history.as_slice();
+
+        self.phases.iter().fold(0.0f32, |peak, phase| {
+            let interpolated: f32 = phase.iter().zip(samples).map(|(tap, x)| tap * x).sum();
+            peak.max(interpolated.abs())
+        })
+    }
+}
+
+/// Split a Kaiser windowed sinc interpolation filter into its polyphase components. The first
+/// phase lines up with the original samples.
+fn interpolation_phases() -> [[f32; TAPS_PER_PHASE]; OVERSAMPLING_FACTOR] {
+    let center = (TAPS_PER_PHASE * OVERSAMPLING_FACTOR / 2) as f32;
+    let half_length = center;
+
+    let mut phases = [[0.0; TAPS_PER_PHASE]; OVERSAMPLING_FACTOR];
+    for (phase_idx, phase) in phases.iter_mut().enumerate() {
+        for (tap_idx, tap) in phase.iter_mut().enumerate() {
+            let offset = (tap_idx * OVERSAMPLING_FACTOR + phase_idx) as f32 - center;
+            let position = offset / OVERSAMPLING_FACTOR as f32;
+            let sinc = if position == 0.0 {
+                1.0
+            } else {
+                (PI * position).sin() / (PI * position)
+            };
+
+            *tap = sinc * kaiser_window(offset / half_length, KAISER_BETA);
+        }
+
+        // Every phase should pass DC at unity gain
+        let sum: f32 = phase.iter().sum();
+        phase.iter_mut().for_each(|tap| *tap /= sum);
+    }
+
+    phases
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn impulse_peaks_after_latency() {
+        assert_eq!(TRUE_PEAK_LATENCY, 6);
+
+        let mut detector = TruePeakDetector::default();
+        detector.initialize(1);
+        let outputs: Vec<f32> = (0..TAPS_PER_PHASE * 2)
+            .map(|sample_idx| detector.process(0, if sample_idx == 0 { 1.0 } else { 0.0 }))
+            .collect();
+
+        let loudest_idx = (0..outputs.len())
+            .max_by(|a, b| outputs[*a].total_cmp(&outputs[*b]))
+            .unwrap();
+        assert_eq!(loudest_idx, TRUE_PEAK_LATENCY);
+        let loudest = outputs[loudest_idx];
+        assert!((loudest - 1.0).abs() < 1e-6, "{loudest}");
+    }
+
+    #[test]
+    fn finds_inter_sample_peaks() {
+        // A sine at a quarter of the sample rate, sampled halfway between its peaks and its zero
+        // crossings, never has a sample above -3 dB
+        let mut detector = TruePeakDetector::default();
+        detector.initialize(1);
+        let mut sample_peak = 0.0f32;
+        let mut true_peak = 0.0f32;
+        for sample_idx in 0..1000 {
+            let phase = PI / 2.0 * sample_idx as f32 + PI / 4.0;
+            sample_peak = sample_peak.max(phase.sin().abs());
+            true_peak = true_peak.max(detector.process(0, phase.sin()));
+        }
+
+        assert!((sample_peak - 0.5f32.sqrt()).abs() < 1e-3, "{sample_peak}");
+        // The short interpolation kernel ripples a little this close to Nyquist
+        let error_db = 20.0 * true_peak.log10();
+        assert!(error_db.abs() < 0.2, "{error_db} dB");
+    }
+}
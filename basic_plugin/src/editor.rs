@@ -1,5 +1,3 @@
-use atomic_float::AtomicF32;
-
 use nih_plug::editor::ParentWindowHandle;
 use nih_plug::prelude::{util, Editor, GuiContext};
 use nih_plug_iced::*;
@@ -7,13 +5,25 @@ use std::sync::Arc;
 use std::time::Duration;
 use nih_plug_iced::widgets as nih_widgets;
 
+mod gain_reduction;
 pub mod knob;
+mod oscilloscope;
+mod phase;
+mod spectrum;
+mod tuner;
 
 // Custom colors
 const BACKGROUND_DARK: Color = Color::from_rgb(0.12, 0.12, 0.12);
 const BACKGROUND_LIGHTER: Color = Color::from_rgb(0.18, 0.18, 0.18);
 
-use crate::BasicParameters;
+use crate::meters::gain_reduction::DynamicsStage;
+use crate::meters::levels::ChannelLevels;
+use crate::meters::oscilloscope::{Oscilloscope, TIMEBASES_MS};
+use crate::meters::spectrum::SpectrumAnalyzer;
+use crate::meters::tuner::Tuner;
+use crate::meters::Meters;
+use crate::presets::{factory, user, Preset};
+use crate::{BasicParameters, MAX_CHANNELS};
 
 // Plugin init State Static.
 pub(crate) fn default_state() -> Arc<IcedState> {
@@ -36,15 +46,26 @@ impl container::StyleSheet for Style {
 
 pub(crate) fn create(
     params: Arc<BasicParameters>,
-    peak_meter: Arc<AtomicF32>,
+    meters: Arc<Meters>,
     editor_state: Arc<IcedState>,
 ) -> Option<Box<dyn Editor>> {
-    create_iced_editor::<BasicEditor>(editor_state, (params, peak_meter))
+    create_iced_editor::<BasicEditor>(editor_state, (params, meters))
 }
 
 struct HeaderState {
     preset_name: String,
     pick_list_state: pick_list::State<String>,
+    factory_presets: Vec<Preset>,
+    /// The presets from the user preset directory, sorted by name.
+    user_presets: Vec<Preset>,
+    /// The name for saving or renaming a preset.
+    name_input: String,
+    name_input_state: text_input::State,
+    save_state: button::State,
+    rename_state: button::State,
+    delete_state: button::State,
+    /// The outcome of the last save, rename, or delete.
+    status: String,
 }
 
 impl HeaderState {
@@ -53,9 +74,159 @@ impl HeaderState {
     }
     
     fn new() -> Self {
+        let factory_presets = factory::presets();
+        // Factory presets take precedence over user presets with the same name
+        let mut user_presets = user::load_all();
+        user_presets.retain(|preset| {
+            !factory_presets
+                .iter()
+                .any(|factory_preset| factory_preset.name == preset.name)
+        });
+
         Self {
             preset_name: "Default".to_string(),
             pick_list_state: pick_list::State::default(),
+            factory_presets,
+            user_presets,
+            name_input: String::new(),
+            name_input_state: text_input::State::default(),
+            save_state: button::State::default(),
+            rename_state: button::State::default(),
+            delete_state: button::State::default(),
+            status: String::new(),
+        }
+    }
+
+    /// The names shown in the preset pick list, with the factory presets first.
+    fn preset_names(&self) -> Vec<String> {
+        self.factory_presets
+            .iter()
+            .chain(&self.user_presets)
+            .map(|preset| preset.name.clone())
+            .collect()
+    }
+
+    fn find_preset(&self, name: &str) -> Option<&Preset> {
+        self.factory_presets
+            .iter()
+            .chain(&self.user_presets)
+            .find(|preset| preset.name == name)
+    }
+
+    fn user_preset_idx(&self, name: &str) -> Option<usize> {
+        self.user_presets
+            .iter()
+            .position(|preset| preset.name == name)
+    }
+
+    /// The user preset that's stored in the same file a preset called `name` would be stored in.
+    fn user_preset_in_file(&self, name: &str) -> Option<usize> {
+        self.user_presets
+            .iter()
+            .position(|preset| user::same_file(&preset.name, name))
+    }
+
+    /// The trimmed name from the name field, or an explanation of why it can't be used for a user
+    /// preset.
+    fn new_preset_name(&self) -> Result<String, String> {
+        let name = self.name_input.trim();
+        if name.is_empty() {
+            Err(String::from("Enter a name for the preset"))
+        } else if self
+            .factory_presets
+            .iter()
+            .any(|preset| preset.name == name)
+        {
+            Err(format!("'{name}' is a factory preset"))
+        } else {
+            Ok(name.to_string())
+        }
+    }
+
+    /// Save the current parameter values as a user preset with the name from the name field,
+    /// overwriting the user preset with that name if there is one.
+    fn save_preset(&mut self, params: &BasicParameters) {
+        let name = match self.new_preset_name() {
+            Ok(name) => name,
+            Err(err) => {
+                self.status = err;
+                return;
+            }
+        };
+        // A different name that maps to the same file would silently replace another preset
+        let existing_idx = self.user_preset_in_file(&name);
+        if let Some(idx) = existing_idx {
+            if self.user_presets[idx].name != name {
+                self.status = format!("'{name}' would overwrite '{}'", self.user_presets[idx].name);
+                return;
+            }
+        }
+
+        let preset = Preset::capture(&name, params);
+        if let Err(err) = user::save(&preset) {
+            self.status = format!("Couldn't save '{name}': {err}");
+            return;
+        }
+
+        match existing_idx {
+            Some(idx) => self.user_presets[idx] = preset,
+            None => {
+                self.user_presets.push(preset);
+                user::sort(&mut self.user_presets);
+            }
+        }
+        self.status = format!("Saved '{name}'");
+        self.preset_name = name;
+    }
+
+    /// Give the selected user preset the name from the name field.
+    fn rename_preset(&mut self) {
+        let Some(idx) = self.user_preset_idx(&self.preset_name) else {
+            self.status = String::from("Select a user preset to rename");
+            return;
+        };
+        let new_name = match self.new_preset_name() {
+            Ok(new_name) => new_name,
+            Err(err) => {
+                self.status = err;
+                return;
+            }
+        };
+        if let Some(other_idx) = self
+            .user_preset_in_file(&new_name)
+            .filter(|&other_idx| other_idx != idx)
+        {
+            let other_name = &self.user_presets[other_idx].name;
+            self.status = format!("'{new_name}' would overwrite '{other_name}'");
+            return;
+        }
+
+        match user::rename(&self.user_presets[idx], &new_name) {
+            Ok(renamed) => {
+                self.user_presets[idx] = renamed;
+                user::sort(&mut self.user_presets);
+                self.status = format!("Renamed '{}' to '{new_name}'", self.preset_name);
+                self.preset_name = new_name;
+            }
+            Err(err) => self.status = format!("Couldn't rename '{}': {err}", self.preset_name),
+        }
+    }
+
+    /// Delete the selected user preset. The parameters keep their current values.
+    fn delete_preset(&mut self) {
+        let Some(idx) = self.user_preset_idx(&self.preset_name) else {
+            self.status = String::from("Select a user preset to delete");
+            return;
+        };
+
+        match user::delete(&self.preset_name) {
+            Ok(()) => {
+                self.user_presets.remove(idx);
+                self.status = format!("Deleted '{}'", self.preset_name);
+                // Nothing is selected until a preset is loaded or saved again
+                self.preset_name.clear();
+            }
+            Err(err) => self.status = format!("Couldn't delete '{}': {err}", self.preset_name),
         }
     }
 }
@@ -64,16 +235,71 @@ struct BasicEditor {
     context: Arc<dyn GuiContext>,
     header_state: HeaderState,
     params: Arc<BasicParameters>, 
-    peak_meter: Arc<AtomicF32>,
+    meters: Arc<Meters>,
+    spectrum: SpectrumAnalyzer,
+    oscilloscope: Oscilloscope,
+    tuner: Tuner,
+    /// The index into [`TIMEBASES_MS`] for the oscilloscope's current zoom level.
+    oscilloscope_timebase_idx: usize,
     gain_slider_state: nih_widgets::param_slider::State,
-    peak_meter_state: nih_widgets::peak_meter::State,
+    input_meter_states: [nih_widgets::peak_meter::State; MAX_CHANNELS],
+    output_meter_states: [nih_widgets::peak_meter::State; MAX_CHANNELS],
+    gain_reduction_holds: [gain_reduction::PeakHold; DynamicsStage::ALL.len()],
+    loudness_reset_state: button::State,
+    true_peak_reset_state: button::State,
+    zoom_in_state: button::State,
+    zoom_out_state: button::State,
+    controls_state: nih_widgets::generic_ui::State<nih_widgets::generic_ui::GenericSlider>,
+    /// The panel shown below the header.
+    panel: Panel,
+    meters_tab_state: button::State,
+    controls_tab_state: button::State,
+    meters_scroll_state: scrollable::State,
     
 }
 
+/// The editor shows either the meters or the parameter controls below the header, so neither has
+/// to share the window's height with the other.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Panel {
+    Meters,
+    Controls,
+}
+
+impl Panel {
+    fn name(self) -> &'static str {
+        match self {
+            Panel::Meters => "Meters",
+            Panel::Controls => "Controls",
+        }
+    }
+}
+
 // Define Message enum for handling preset selection
 #[derive(Debug, Clone)]
 enum Message {
     PresetSelected(String),
+    /// The text in the preset name field changed.
+    PresetNameChanged(String),
+    /// Save the current settings as a user preset, using the name from the name field.
+    SavePreset,
+    /// Rename the selected user preset to the name from the name field.
+    RenamePreset,
+    /// Delete the selected user preset.
+    DeletePreset,
+    /// Restart the integrated loudness and loudness range measurements.
+    ResetLoudness,
+    /// Clear the output's held maximum true peak.
+    ResetTruePeak,
+    /// Show a shorter time span in the oscilloscope.
+    ZoomIn,
+    /// Show a longer time span in the oscilloscope.
+    ZoomOut,
+    /// Switch the panel below the header.
+    ShowPanel(Panel),
+    /// Sent once per frame to feed the latest audio to the spectrum analyzer, the oscilloscope,
+    /// and the tuner.
+    AnalyzeFrame,
     
     ParamUpdate(nih_widgets::ParamMessage),
 }
@@ -81,10 +307,10 @@ enum Message {
 impl IcedEditor for BasicEditor {
     type Executor = executor::Default;
     type Message = Message;
-    type InitializationFlags = (Arc<BasicParameters>, Arc<AtomicF32>);
+    type InitializationFlags = (Arc<BasicParameters>, Arc<Meters>);
 
     fn new(
-        (params, peak_meter):  Self::InitializationFlags,
+        (params, meters): Self::InitializationFlags,
         context: Arc<dyn GuiContext>,
     ) -> (Self, Command<Self::Message>) {
         (
@@ -92,9 +318,25 @@ impl IcedEditor for BasicEditor {
                 context,
                 header_state: HeaderState::new(),
                 params,
-                peak_meter,
+                meters,
+                spectrum: SpectrumAnalyzer::default(),
+                oscilloscope: Oscilloscope::default(),
+                tuner: Tuner::default(),
+                // 20 ms fits a couple of cycles of a low bass note
+                oscilloscope_timebase_idx: 3,
                 gain_slider_state: Default::default(),
-                peak_meter_state: Default::default(),
+                input_meter_states: Default::default(),
+                output_meter_states: Default::default(),
+                gain_reduction_holds: Default::default(),
+                loudness_reset_state: Default::default(),
+                true_peak_reset_state: Default::default(),
+                zoom_in_state: Default::default(),
+                zoom_out_state: Default::default(),
+                controls_state: Default::default(),
+                panel: Panel::Meters,
+                meters_tab_state: Default::default(),
+                controls_tab_state: Default::default(),
+                meters_scroll_state: Default::default(),
             },
             Command::none(),
         )
@@ -103,6 +345,15 @@ impl IcedEditor for BasicEditor {
     fn context(&self) -> &dyn GuiContext {
         self.context.as_ref()
     }
+
+    fn subscription(
+        &self,
+        window_subscription: &mut WindowSubs<Self::Message>,
+    ) -> Subscription<Self::Message> {
+        window_subscription.on_frame = Some(Message::AnalyzeFrame);
+
+        Subscription::none()
+    }
     
     // Update user states 
     fn update(
@@ -112,9 +363,40 @@ impl IcedEditor for BasicEditor {
     ) -> Command<Self::Message> {
         match message {
             Message::PresetSelected(preset_name) => {
-                // Update the selected preset name
+                if let Some(preset) = self.header_state.find_preset(&preset_name) {
+                    preset.apply(&self.params, self.context.as_ref());
+                }
+
+                // Update the selected preset name, and offer it for renaming or overwriting
+                self.header_state.name_input = preset_name.clone();
                 self.header_state.preset_name = preset_name;
             },
+            Message::PresetNameChanged(name) => self.header_state.name_input = name,
+            Message::SavePreset => self.header_state.save_preset(&self.params),
+            Message::RenamePreset => self.header_state.rename_preset(),
+            Message::DeletePreset => self.header_state.delete_preset(),
+            // The audio thread picks this up at the start of the next buffer
+            Message::ResetLoudness => self.meters.loudness.request_reset(),
+            Message::ResetTruePeak => self.meters.output_levels.request_max_reset(),
+            Message::ZoomIn => {
+                self.oscilloscope_timebase_idx = self.oscilloscope_timebase_idx.saturating_sub(1);
+            }
+            Message::ZoomOut => {
+                self.oscilloscope_timebase_idx =
+                    (self.oscilloscope_timebase_idx + 1).min(TIMEBASES_MS.len() - 1);
+            }
+            Message::ShowPanel(panel) => self.panel = panel,
+            // The analysis only runs here and not in `view()`, so redraws just show the results
+            Message::AnalyzeFrame => {
+                self.spectrum.update(&self.meters.spectrum);
+                self.tuner.update(&self.meters.tuner);
+                let timebase_ms = TIMEBASES_MS[self.oscilloscope_timebase_idx];
+                self.oscilloscope.update(&self.meters.oscilloscope, timebase_ms);
+                let stages = DynamicsStage::ALL.into_iter().zip(&mut self.gain_reduction_holds);
+                for (stage, peak_hold) in stages {
+                    peak_hold.update(util::gain_to_db(self.meters.gain_reduction.get(stage)));
+                }
+            }
             // Message Gain and Peakmeter state change 
             Message::ParamUpdate(message) => self.handle_param_message(message),
         }
@@ -123,7 +405,7 @@ impl IcedEditor for BasicEditor {
 
     fn view(&mut self) -> Element<'_, Self::Message> {
         // Define preset options
-        let preset_options = vec!["Default".to_string(), "Preset-1".to_string(), "Preset-2".to_string()];
+        let preset_options = self.header_state.preset_names();
         
         // Get the current gain value TODO used the values or not 
         //let gain_value = self.params.gain.value();
@@ -133,7 +415,231 @@ impl IcedEditor for BasicEditor {
         //let knob_svg =Handle defined in the knob.rs load_svg_knob TODO imploment Interactive rotation of the button Knob. 
         //let knob_svg = knob::load_knob_svg();
 
+        let pitch = self.tuner.pitch();
+        let (note_text, pitch_text) = match pitch {
+            Some(pitch) => (
+                format!("{}{}", pitch.note_name(), pitch.octave),
+                format!("{:+.0} cents, {:.1} Hz", pitch.cents, pitch.frequency),
+            ),
+            None => (String::from("-"), String::from("No pitch")),
+        };
+        let timebase_ms = TIMEBASES_MS[self.oscilloscope_timebase_idx];
+        let correlation = self.meters.output_correlation.get();
+
+        // One gain reduction bar per dynamics stage, with the current reduction below it
+        let mut gain_reduction_meters = Row::new().spacing(15);
+        for (stage, peak_hold) in DynamicsStage::ALL.into_iter().zip(&self.gain_reduction_holds) {
+            let gain_reduction_db = peak_hold.current_db();
+            let peak_hold_db = peak_hold.held_db();
+
+            gain_reduction_meters = gain_reduction_meters.push(
+                Column::new()
+                    .spacing(5)
+                    .align_items(Alignment::Center)
+                    .push(gain_reduction::GainReductionBar::new(gain_reduction_db, peak_hold_db))
+                    .push(Text::new(stage.name()).size(14))
+                    .push(Text::new(&format!("{gain_reduction_db:.1}")).size(14)),
+            );
+        }
+
+        let input_meters = level_meters(&self.meters.input_levels, &mut self.input_meter_states);
+        let output_meters = level_meters(&self.meters.output_levels, &mut self.output_meter_states);
+        let auto_gain_db = util::gain_to_db(self.meters.auto_gain.get());
+
+
+        // The meters are taller than the window, so they scroll. The controls already scroll on
+        // their own.
+        let panel: Element<'_, Message> = match self.panel {
+            Panel::Meters => Scrollable::new(&mut self.meters_scroll_state)
+                .width(Length::Fill)
+                .height(Length::Fill)
+                .push(
+                    Container::new(
+                        Column::new()
+                            .spacing(20)
+                            .width(Length::Fill)
+
+                            // Gain and Peak 
+                            .push(
+                                Text::new("Gain")
+                                    .height(20.into())
+                                    .width(Length::Fill)
+                                    .horizontal_alignment(alignment::Horizontal::Center)
+                                    .vertical_alignment(alignment::Vertical::Center),
+                            )
+
+                            // Gain Slider 
+                            .push(
+                                nih_widgets::ParamSlider::new(
+                                    &mut self.gain_slider_state,
+                                    &self.params.gain,
+                                )
+                                .map(Message::ParamUpdate),
+                            )
+                            .push(Space::with_height(10.into()))
+
+                            // Tuner for the bass on the input
+                            .push(
+                                Row::new()
+                                    .spacing(15)
+                                    .align_items(Alignment::Center)
+                                    .push(Text::new(&note_text).size(32).width(Length::Units(70)))
+                                    .push(
+                                        Column::new()
+                                            .spacing(5)
+                                            .push(tuner::CentsBar::new(
+                                                pitch.map(|pitch| pitch.cents),
+                                            ))
+                                            .push(Text::new(&pitch_text)),
+                                    ),
+                            )
+
+                            // Input and output meters, so the processing can be compared at a
+                            // glance
+                            .push(Text::new("Input"))
+                            .push(input_meters)
+                            .push(Text::new("Output"))
+                            .push(output_meters)
+                            .push(
+                                Text::new(if self.params.auto_gain.value() {
+                                    format!("Auto Gain: {auto_gain_db:+.1} dB")
+                                } else {
+                                    String::from("Auto Gain: Off")
+                                })
+                                .width(Length::Fill)
+                                .horizontal_alignment(alignment::Horizontal::Center),
+                            )
+
+                            // The highest true peak since the start, which catches inter-sample
+                            // overs. Clicking it starts over.
+                            .push(
+                                Container::new(
+                                    Button::new(
+                                        &mut self.true_peak_reset_state,
+                                        Text::new(&format!(
+                                            "True Peak Max: {:.1} dBTP",
+                                            util::gain_to_db(
+                                                self.meters.output_levels.max_true_peak()
+                                            )
+                                        )),
+                                    )
+                                    .on_press(Message::ResetTruePeak),
+                                )
+                                .width(Length::Fill)
+                                .center_x(),
+                            )
+
+                            // EBU R128 loudness
+                            .push(
+                                Row::new()
+                                    .spacing(15)
+                                    .align_items(Alignment::Center)
+                                    .push(Text::new(&format!(
+                                        "M {} LUFS",
+                                        format_loudness(self.meters.loudness.momentary())
+                                    )))
+                                    .push(Text::new(&format!(
+                                        "S {} LUFS",
+                                        format_loudness(self.meters.loudness.short_term())
+                                    )))
+                                    .push(Text::new(&format!(
+                                        "I {} LUFS",
+                                        format_loudness(self.meters.loudness.integrated())
+                                    )))
+                                    .push(Text::new(&format!(
+                                        "LRA {} LU",
+                                        format_loudness(self.meters.loudness.range())
+                                    )))
+                                    .push(
+                                        Button::new(
+                                            &mut self.loudness_reset_state,
+                                            Text::new("Reset"),
+                                        )
+                                        .on_press(Message::ResetLoudness),
+                                    ),
+                            )
 
+                            // Input (line) and output (filled) spectrum
+                            .push(spectrum::SpectrumView::new(
+                                self.spectrum.input_db(),
+                                self.spectrum.output_db(),
+                            ))
+
+                            // Output waveform, with zoom controls for the time span
+                            .push(
+                                Row::new()
+                                    .spacing(10)
+                                    .align_items(Alignment::Center)
+                                    .push(
+                                        Button::new(&mut self.zoom_out_state, Text::new("-"))
+                                            .on_press(Message::ZoomOut),
+                                    )
+                                    .push(Text::new(&format!("{timebase_ms} ms")))
+                                    .push(
+                                        Button::new(&mut self.zoom_in_state, Text::new("+"))
+                                            .on_press(Message::ZoomIn),
+                                    )
+                                    .push(Text::new(if self.oscilloscope.triggered() {
+                                        "Triggered"
+                                    } else {
+                                        "Free running"
+                                    })),
+                            )
+                            .push(oscilloscope::OscilloscopeView::new(&self.oscilloscope))
+
+                            // Stereo image and mono compatibility
+                            .push(
+                                Row::new()
+                                    .spacing(15)
+                                    .align_items(Alignment::Center)
+                                    .push(phase::Goniometer::new(&self.oscilloscope))
+                                    .push(
+                                        Column::new()
+                                            .spacing(5)
+                                            .push(Text::new(&format!(
+                                                "Correlation {correlation:+.2}"
+                                            )))
+                                            .push(phase::CorrelationBar::new(correlation)),
+                                    ),
+                            )
+
+                            // Gain reduction of the gate, compressor, ducker, and limiter
+                            .push(Text::new("Gain Reduction (dB)"))
+                            .push(gain_reduction_meters),
+                    )
+                    .width(Length::Fill)
+                    .padding(10)
+                    .style(Style {
+                        background: Some(Background::Color(BACKGROUND_LIGHTER)),
+                    }),
+                )
+                .into(),
+
+            // Main Controls
+            Panel::Controls => Container::new(
+                Column::new()
+                    .spacing(10)
+                    .width(Length::Fill)
+                    .push(
+                        Text::new("Controls")
+                            .size(24)
+                            .color(Color::from_rgb(0.9, 0.9, 0.9))  // Almost white text
+                    )
+                    // Sliders for all of the plugin parameters
+                    .push(
+                        nih_widgets::GenericUi::new(&mut self.controls_state, self.params.clone())
+                            .pad_scrollbar()
+                            .map(Message::ParamUpdate),
+                    )
+            )
+            .width(Length::Fill)
+            .height(Length::Fill)
+            .padding(15)
+            .style(Style {
+                background: Some(Background::Color(BACKGROUND_LIGHTER)),
+            })
+            .into(),
+        };
 
         let content = Column::new()
             .padding(10)
@@ -142,84 +648,67 @@ impl IcedEditor for BasicEditor {
             
             // Header Section 
             .push(
-                Container::new(
-                    Column::new()
-                        .spacing(20)
-                        .width(Length::Fill)
-                        .push(
-                            Text::new("PhatBass VST")
-                                .size(32)
-                                .color(Color::from_rgb(0.0, 0.7, 1.0))
-                        )
-                        .push(
-                            Row::new()
-                                .spacing(10)
-                                .push(Text::new("v1.0.0"))
-                                .push(Space::with_width(Length::Fill)) // Add flexible space
-                        )
-
-
-            // Gain and Peak 
-            .push(
-                Text::new("Gain")
-                    .height(20.into())
-                    .width(Length::Fill)
-                    .horizontal_alignment(alignment::Horizontal::Center)
-                    .vertical_alignment(alignment::Vertical::Center),
+                Row::new()
+                    .spacing(10)
+                    .align_items(Alignment::End)
+                    .push(
+                        Text::new("PhatBass VST")
+                            .size(32)
+                            .color(Color::from_rgb(0.0, 0.7, 1.0))
+                    )
+                    .push(Text::new("v1.0.0"))
             )
-            
-            // Gain Slider 
-            .push(
-                nih_widgets::ParamSlider::new(&mut self.gain_slider_state, &self.params.gain)
-                    .map(Message::ParamUpdate),
-            )            
-            .push(Space::with_height(10.into()))
-            
-            // Peak_Meter  
-            .push(
-                nih_widgets::PeakMeter::new(
-                    &mut self.peak_meter_state,
-                    util::gain_to_db(self.peak_meter.load(std::sync::atomic::Ordering::Relaxed)),
-                )
-                .hold_time(Duration::from_millis(600)),
-            )            
 
-                )
-                .padding(10)
-                .style(Style {
-                    background: Some(Background::Color(BACKGROUND_LIGHTER)),
-                })
+            // Presets Dropdown, and saving, renaming, and deleting user presets
+            .push(
+                Row::new()
+                    .spacing(10)
+                    .align_items(Alignment::Center)
+                    .push(Text::new("Preset"))
+                    .push(
+                        PickList::new(
+                            &mut self.header_state.pick_list_state,
+                            preset_options,
+                            Some(&self.header_state.preset_name)
+                                .filter(|preset_name| !preset_name.is_empty())
+                                .cloned(),
+                            Message::PresetSelected
+                        )
+                    )
+                    .push(
+                        TextInput::new(
+                            &mut self.header_state.name_input_state,
+                            "Preset name",
+                            &self.header_state.name_input,
+                            Message::PresetNameChanged,
+                        )
+                        .on_submit(Message::SavePreset)
+                        .padding(5)
+                        .width(Length::Units(200)),
+                    )
+                    .push(
+                        Button::new(&mut self.header_state.save_state, Text::new("Save"))
+                            .on_press(Message::SavePreset),
+                    )
+                    .push(
+                        Button::new(&mut self.header_state.rename_state, Text::new("Rename"))
+                            .on_press(Message::RenamePreset),
+                    )
+                    .push(
+                        Button::new(&mut self.header_state.delete_state, Text::new("Delete"))
+                            .on_press(Message::DeletePreset),
+                    ),
             )
+            .push(Text::new(&self.header_state.status))
 
-            // Presets Dropdown 
-            .push(Text::new(&format!("PRESET: {}", self.header_state.preset_name)))            
-            // Pick List DropDown 
+            // Switches between the meters and the controls below the header
             .push(
-                PickList::new(
-                    &mut self.header_state.pick_list_state,
-                    preset_options,
-                    Some(self.header_state.preset_name.clone()),
-                    Message::PresetSelected
-                )
+                Row::new()
+                    .spacing(10)
+                    .push(panel_button(&mut self.meters_tab_state, Panel::Meters, self.panel))
+                    .push(panel_button(&mut self.controls_tab_state, Panel::Controls, self.panel)),
             )
-            // Main Controls
-            .push(
-                Container::new(
-                    Column::new()
-                        .spacing(10)
-                        .width(Length::Fill)
-                        .push(
-                            Text::new("Controls")
-                                .size(24)
-                                .color(Color::from_rgb(0.9, 0.9, 0.9))  // Almost white text
-                        )                
-                )
-                .width(Length::Fill)
-                .padding(15)
-                .style(Style {
-                    background: Some(Background::Color(BACKGROUND_LIGHTER)),
-                })
-            );            
+            .push(panel);
 
         Container::new(content)
             .width(Length::Fill)
@@ -229,4 +718,77 @@ impl IcedEditor for BasicEditor {
             })
             .into()
     }
-}
\ No newline at end of file
+}
+
+/// One peak meter per channel, with the channel's RMS level next to it.
+fn level_meters<'a>(
+    levels: &ChannelLevels,
+    meter_states: &'a mut [nih_widgets::peak_meter::State; MAX_CHANNELS],
+) -> Column<'a, Message> {
+    let num_channels = levels.num_channels().min(MAX_CHANNELS);
+    let mut meters = Column::new().spacing(5);
+    for (channel_idx, meter_state) in meter_states[..num_channels].iter_mut().enumerate() {
+        let peak = levels.peak(channel_idx);
+
+        meters = meters.push(
+            Row::new()
+                .spacing(10)
+                .align_items(Alignment::Center)
+                .push(Text::new(channel_name(num_channels, channel_idx)).width(Length::Units(40)))
+                .push(
+                    nih_widgets::PeakMeter::new(meter_state, util::gain_to_db(peak))
+                        .hold_time(Duration::from_millis(600)),
+                )
+                .push(
+                    Text::new(&format!(
+                        "RMS {:.1} dB",
+                        util::gain_to_db(levels.rms(channel_idx))
+                    ))
+                    .width(Length::Units(110)),
+                ),
+        );
+    }
+
+    meters
+}
+
+/// A button that switches to `panel`. The button for the panel that's already shown is disabled.
+fn panel_button(state: &mut button::State, panel: Panel, shown: Panel) -> Button<'_, Message> {
+    let button = Button::new(state, Text::new(panel.name()));
+    if panel == shown {
+        button
+    } else {
+        button.on_press(Message::ShowPanel(panel))
+    }
+}
+
+/// The label for a channel meter.
+fn channel_name(num_channels: usize, channel_idx: usize) -> &'static str {
+    match (num_channels, channel_idx) {
+        (1, _) => "Mono",
+        (_, 0) => "L",
+        _ => "R",
+    }
+}
+
+/// Draw a solid rectangle. This is what the custom meter widgets are built from.
+fn fill_rectangle<R: renderer::Renderer>(renderer: &mut R, bounds: Rectangle, color: Color) {
+    renderer.fill_quad(
+        renderer::Quad {
+            bounds,
+            border_radius: 0.0,
+            border_width: 0.0,
+            border_color: Color::TRANSPARENT,
+        },
+        Background::Color(color),
+    );
+}
+
+/// Format a loudness reading, which is negative infinity until there's enough audio to measure.
+fn format_loudness(value: f32) -> String {
+    if value.is_finite() {
+        format!("{value:.1}")
+    } else {
+        String::from("-inf")
+    }
+}
@@ -0,0 +1,131 @@
+use nih_plug_iced::{
+    layout, renderer, Color, Element, Layout, Length, Point, Rectangle, Size, Widget,
+};
+use std::time::{Duration, Instant};
+
+use super::fill_rectangle;
+
+/// The deepest gain reduction the bars can show. Anything deeper fills the whole bar.
+const RANGE_DB: f32 = 24.0;
+/// How long the peak hold marker stays at the deepest gain reduction.
+const HOLD_TIME: Duration = Duration::from_millis(1500);
+
+const BACKGROUND_COLOR: Color = Color::from_rgb(0.08, 0.08, 0.08);
+const BAR_COLOR: Color = Color::from_rgb(1.0, 0.45, 0.1);
+const HOLD_COLOR: Color = Color::from_rgb(0.9, 0.9, 0.9);
+
+/// Keeps the deepest gain reduction around for a moment, so short spikes can still be read. This
+/// also stores the current gain reduction, so the bar and the marker come from the same frame.
+#[derive(Default)]
+pub struct PeakHold {
+    current_db: f32,
+    held_db: f32,
+    held_since: Option<Instant>,
+}
+
+impl PeakHold {
+    /// Update the hold with the current gain reduction in decibels, which is zero or negative.
+    pub fn update(&mut self, gain_reduction_db: f32) {
+        self.current_db = gain_reduction_db;
+
+        let now = Instant::now();
+        let expired = self
+            .held_since
+            .is_none_or(|held_since| now - held_since >= HOLD_TIME);
+        if gain_reduction_db <= self.held_db || expired {
+            self.held_db = gain_reduction_db;
+            self.held_since = Some(now);
+        }
+    }
+
+    /// The gain reduction from the last update.
+    pub fn current_db(&self) -> f32 {
+        self.current_db
+    }
+
+    /// The deepest gain reduction within the hold time.
+    pub fn held_db(&self) -> f32 {
+        self.held_db
+    }
+}
+
+/// A vertical bar that fills downwards from the top as the gain reduction increases, with a
+/// marker for the held peak.
+pub struct GainReductionBar {
+    gain_reduction_db: f32,
+    peak_hold_db: f32,
+
+    width: Length,
+    height: Length,
+}
+
+impl GainReductionBar {
+    /// Both values are in decibels, and are zero or negative.
+    pub fn new(gain_reduction_db: f32, peak_hold_db: f32) -> Self {
+        Self {
+            gain_reduction_db,
+            peak_hold_db,
+
+            width: Length::Units(16),
+            height: Length::Units(100),
+        }
+    }
+}
+
+impl<Message, R> Widget<Message, R> for GainReductionBar
+where
+    R: renderer::Renderer,
+{
+    fn width(&self) -> Length {
+        self.width
+    }
+
+    fn height(&self) -> Length {
+        self.height
+    }
+
+    fn layout(&self, _renderer: &R, limits: &layout::Limits) -> layout::Node {
+        let limits = limits.width(self.width).height(self.height);
+        let size = limits.resolve(Size::ZERO);
+
+        layout::Node::new(size)
+    }
+
+    fn draw(
+        &self,
+        renderer: &mut R,
+        _style: &renderer::Style,
+        layout: Layout<'_>,
+        _cursor_position: Point,
+        _viewport: &Rectangle,
+    ) {
+        let bounds = layout.bounds();
+        fill_rectangle(renderer, bounds, BACKGROUND_COLOR);
+
+        let depth = |gain_reduction_db: f32| {
+            (-gain_reduction_db / RANGE_DB).clamp(0.0, 1.0) * bounds.height
+        };
+
+        let bar = Rectangle {
+            height: depth(self.gain_reduction_db),
+            ..bounds
+        };
+        fill_rectangle(renderer, bar, BAR_COLOR);
+
+        let hold_depth = depth(self.peak_hold_db);
+        if hold_depth > 0.0 {
+            let hold_marker = Rectangle {
+                y: bounds.y + (hold_depth - 2.0).max(0.0),
+                height: 2.0,
+                ..bounds
+            };
+            fill_rectangle(renderer, hold_marker, HOLD_COLOR);
+        }
+    }
+}
+
+impl<'a, Message> From<GainReductionBar> for Element<'a, Message> {
+    fn from(widget: GainReductionBar) -> Self {
+        Element::new(widget)
+    }
+}
@@ -0,0 +1,111 @@
+use nih_plug_iced::{
+    layout, renderer, Color, Element, Layout, Length, Point, Rectangle, Size, Widget,
+};
+
+use super::fill_rectangle;
+use crate::meters::oscilloscope::Oscilloscope;
+
+/// The vertical axis goes a bit past full scale so clipped peaks are easy to spot.
+const VERTICAL_RANGE: f32 = 1.2;
+
+const BACKGROUND_COLOR: Color = Color::from_rgb(0.08, 0.08, 0.08);
+const GRID_COLOR: Color = Color::from_rgb(0.25, 0.25, 0.25);
+const FULL_SCALE_COLOR: Color = Color::from_rgb(0.5, 0.15, 0.15);
+/// The trace colors for the left and right channels. A mono trace uses the first one.
+const CHANNEL_COLORS: [Color; 2] = [
+    Color::from_rgba(0.0, 0.7, 1.0, 0.8),
+    Color::from_rgba(1.0, 0.6, 0.1, 0.8),
+];
+
+/// Draws the traces from an [`Oscilloscope`], one on top of the other for every channel.
+pub struct OscilloscopeView<'a> {
+    oscilloscope: &'a Oscilloscope,
+
+    width: Length,
+    height: Length,
+}
+
+impl<'a> OscilloscopeView<'a> {
+    pub fn new(oscilloscope: &'a Oscilloscope) -> Self {
+        Self {
+            oscilloscope,
+
+            width: Length::Fill,
+            height: Length::Units(150),
+        }
+    }
+}
+
+impl<'a, Message, R> Widget<Message, R> for OscilloscopeView<'a>
+where
+    R: renderer::Renderer,
+{
+    fn width(&self) -> Length {
+        self.width
+    }
+
+    fn height(&self) -> Length {
+        self.height
+    }
+
+    fn layout(&self, _renderer: &R, limits: &layout::Limits) -> layout::Node {
+        let limits = limits.width(self.width).height(self.height);
+        let size = limits.resolve(Size::ZERO);
+
+        layout::Node::new(size)
+    }
+
+    fn draw(
+        &self,
+        renderer: &mut R,
+        _style: &renderer::Style,
+        layout: Layout<'_>,
+        _cursor_position: Point,
+        _viewport: &Rectangle,
+    ) {
+        let bounds = layout.bounds();
+        fill_rectangle(renderer, bounds, BACKGROUND_COLOR);
+
+        let sample_y = |sample: f32| {
+            let t = (sample / VERTICAL_RANGE).clamp(-1.0, 1.0);
+            bounds.y + bounds.height * (0.5 - t / 2.0)
+        };
+        let horizontal_line = |y: f32| Rectangle {
+            x: bounds.x,
+            y,
+            width: bounds.width,
+            height: 1.0,
+        };
+
+        fill_rectangle(renderer, horizontal_line(sample_y(0.0)), GRID_COLOR);
+        fill_rectangle(renderer, horizontal_line(sample_y(1.0)), FULL_SCALE_COLOR);
+        fill_rectangle(renderer, horizontal_line(sample_y(-1.0)), FULL_SCALE_COLOR);
+
+        for (channel_idx, color) in CHANNEL_COLORS
+            .iter()
+            .enumerate()
+            .take(self.oscilloscope.num_channels())
+        {
+            let columns = self.oscilloscope.columns(channel_idx);
+            let column_width = bounds.width / columns.len().max(1) as f32;
+            for (column_idx, (min, max)) in columns.iter().enumerate() {
+                // Every column spans at least a pixel so flat parts of the trace stay visible
+                let top = sample_y(*max);
+                let bottom = sample_y(*min).max(top + 1.0);
+                let column = Rectangle {
+                    x: bounds.x + column_idx as f32 * column_width,
+                    y: top,
+                    width: column_width.max(1.0),
+                    height: bottom - top,
+                };
+                fill_rectangle(renderer, column, *color);
+            }
+        }
+    }
+}
+
+impl<'a, Message> From<OscilloscopeView<'a>> for Element<'a, Message> {
+    fn from(widget: OscilloscopeView<'a>) -> Self {
+        Element::new(widget)
+    }
+}
@@ -0,0 +1,194 @@
+use nih_plug_iced::{
+    layout, renderer, Color, Element, Layout, Length, Point, Rectangle, Size, Widget,
+};
+
+use super::fill_rectangle;
+use crate::meters::oscilloscope::Oscilloscope;
+
+/// The number of recent frames the goniometer draws. This is about 40 ms at 48 kHz.
+const GONIOMETER_FRAMES: usize = 2048;
+/// The size of a single point in the goniometer.
+const DOT_SIZE: f32 = 2.0;
+
+const BACKGROUND_COLOR: Color = Color::from_rgb(0.08, 0.08, 0.08);
+const GRID_COLOR: Color = Color::from_rgb(0.25, 0.25, 0.25);
+const DOT_COLOR: Color = Color::from_rgba(0.0, 0.7, 1.0, 0.35);
+const POSITIVE_COLOR: Color = Color::from_rgb(0.2, 0.8, 0.3);
+const NEGATIVE_COLOR: Color = Color::from_rgb(0.9, 0.2, 0.2);
+
+/// A Lissajous display of the recent output. The mid signal points up and the side signal points
+/// sideways, so a mono signal is a vertical line, wide stereo fills the circle, and out of phase
+/// content spreads out horizontally. Left-only signals lean to the upper left.
+pub struct Goniometer<'a> {
+    oscilloscope: &'a Oscilloscope,
+
+    size: u16,
+}
+
+impl<'a> Goniometer<'a> {
+    /// The goniometer draws from the oscilloscope's history, so both show the same audio.
+    pub fn new(oscilloscope: &'a Oscilloscope) -> Self {
+        Self {
+            oscilloscope,
+
+            size: 150,
+        }
+    }
+}
+
+impl<'a, Message, R> Widget<Message, R> for Goniometer<'a>
+where
+    R: renderer::Renderer,
+{
+    fn width(&self) -> Length {
+        Length::Units(self.size)
+    }
+
+    fn height(&self) -> Length {
+        Length::Units(self.size)
+    }
+
+    fn layout(&self, _renderer: &R, limits: &layout::Limits) -> layout::Node {
+        let limits = limits
+            .width(Length::Units(self.size))
+            .height(Length::Units(self.size));
+        let size = limits.resolve(Size::ZERO);
+
+        layout::Node::new(size)
+    }
+
+    fn draw(
+        &self,
+        renderer: &mut R,
+        _style: &renderer::Style,
+        layout: Layout<'_>,
+        _cursor_position: Point,
+        _viewport: &Rectangle,
+    ) {
+        let bounds = layout.bounds();
+        fill_rectangle(renderer, bounds, BACKGROUND_COLOR);
+
+        let center_x = bounds.x + bounds.width / 2.0;
+        let center_y = bounds.y + bounds.height / 2.0;
+        let vertical_axis = Rectangle {
+            x: center_x,
+            y: bounds.y,
+            width: 1.0,
+            height: bounds.height,
+        };
+        let horizontal_axis = Rectangle {
+            x: bounds.x,
+            y: center_y,
+            width: bounds.width,
+            height: 1.0,
+        };
+        fill_rectangle(renderer, vertical_axis, GRID_COLOR);
+        fill_rectangle(renderer, horizontal_axis, GRID_COLOR);
+
+        // A mono layout is shown as identical left and right channels
+        let is_stereo = self.oscilloscope.num_channels() > 1;
+        for frame in self.oscilloscope.recent_frames(GONIOMETER_FRAMES) {
+            let left = frame[0];
+            let right = if is_stereo { frame[1] } else { left };
+            let mid = ((left + right) / 2.0).clamp(-1.0, 1.0);
+            let side = ((right - left) / 2.0).clamp(-1.0, 1.0);
+
+            let dot = Rectangle {
+                x: center_x + side * (bounds.width - DOT_SIZE) / 2.0,
+                y: center_y - mid * (bounds.height - DOT_SIZE) / 2.0,
+                width: DOT_SIZE,
+                height: DOT_SIZE,
+            };
+            fill_rectangle(renderer, dot, DOT_COLOR);
+        }
+    }
+}
+
+impl<'a, Message> From<Goniometer<'a>> for Element<'a, Message> {
+    fn from(widget: Goniometer<'a>) -> Self {
+        Element::new(widget)
+    }
+}
+
+/// A horizontal bar for a correlation reading between -1 and +1. The bar grows from the center,
+/// to the right in green for positive correlation and to the left in red for negative
+/// correlation.
+pub struct CorrelationBar {
+    correlation: f32,
+
+    width: Length,
+    height: Length,
+}
+
+impl CorrelationBar {
+    pub fn new(correlation: f32) -> Self {
+        Self {
+            correlation,
+
+            width: Length::Units(150),
+            height: Length::Units(12),
+        }
+    }
+}
+
+impl<Message, R> Widget<Message, R> for CorrelationBar
+where
+    R: renderer::Renderer,
+{
+    fn width(&self) -> Length {
+        self.width
+    }
+
+    fn height(&self) -> Length {
+        self.height
+    }
+
+    fn layout(&self, _renderer: &R, limits: &layout::Limits) -> layout::Node {
+        let limits = limits.width(self.width).height(self.height);
+        let size = limits.resolve(Size::ZERO);
+
+        layout::Node::new(size)
+    }
+
+    fn draw(
+        &self,
+        renderer: &mut R,
+        _style: &renderer::Style,
+        layout: Layout<'_>,
+        _cursor_position: Point,
+        _viewport: &Rectangle,
+    ) {
+        let bounds = layout.bounds();
+        fill_rectangle(renderer, bounds, BACKGROUND_COLOR);
+
+        let center_x = bounds.x + bounds.width / 2.0;
+        let correlation = self.correlation.clamp(-1.0, 1.0);
+        let bar_width = correlation.abs() * bounds.width / 2.0;
+        let (bar_x, color) = if correlation >= 0.0 {
+            (center_x, POSITIVE_COLOR)
+        } else {
+            (center_x - bar_width, NEGATIVE_COLOR)
+        };
+        let bar = Rectangle {
+            x: bar_x,
+            y: bounds.y,
+            width: bar_width,
+            height: bounds.height,
+        };
+        fill_rectangle(renderer, bar, color);
+
+        let center_tick = Rectangle {
+            x: center_x,
+            y: bounds.y,
+            width: 1.0,
+            height: bounds.height,
+        };
+        fill_rectangle(renderer, center_tick, GRID_COLOR);
+    }
+}
+
+impl<'a, Message> From<CorrelationBar> for Element<'a, Message> {
+    fn from(widget: CorrelationBar) -> Self {
+        Element::new(widget)
+    }
+}
@@ -0,0 +1,132 @@
+use nih_plug_iced::{
+    layout, renderer, Color, Element, Layout, Length, Point, Rectangle, Size, Widget,
+};
+
+use super::fill_rectangle;
+use crate::meters::spectrum::{FLOOR_DB, MAX_FREQUENCY, MIN_FREQUENCY};
+
+/// The loudest level on the analyzer's vertical axis.
+const CEILING_DB: f32 = 0.0;
+/// The horizontal grid lines, in decibels.
+const GRID_LEVELS_DB: [f32; 4] = [-20.0, -40.0, -60.0, -80.0];
+/// The vertical grid lines, in Hertz.
+const GRID_FREQUENCIES: [f32; 9] = [
+    50.0, 100.0, 200.0, 500.0, 1_000.0, 2_000.0, 5_000.0, 10_000.0, 20_000.0,
+];
+
+const BACKGROUND_COLOR: Color = Color::from_rgb(0.08, 0.08, 0.08);
+const GRID_COLOR: Color = Color::from_rgb(0.25, 0.25, 0.25);
+const INPUT_COLOR: Color = Color::from_rgb(1.0, 0.6, 0.1);
+const OUTPUT_COLOR: Color = Color::from_rgba(0.0, 0.7, 1.0, 0.6);
+
+/// Draws the input and output spectra from a
+/// [`SpectrumAnalyzer`][crate::meters::spectrum::SpectrumAnalyzer] on top of each other. The
+/// output is drawn as a filled area, and the input as a line over it.
+pub struct SpectrumView<'a> {
+    input_db: &'a [f32],
+    output_db: &'a [f32],
+
+    width: Length,
+    height: Length,
+}
+
+impl<'a> SpectrumView<'a> {
+    /// Both curves need to have one magnitude for every point on the analyzer's frequency axis.
+    pub fn new(input_db: &'a [f32], output_db: &'a [f32]) -> Self {
+        Self {
+            input_db,
+            output_db,
+
+            width: Length::Fill,
+            height: Length::Units(180),
+        }
+    }
+}
+
+impl<'a, Message, R> Widget<Message, R> for SpectrumView<'a>
+where
+    R: renderer::Renderer,
+{
+    fn width(&self) -> Length {
+        self.width
+    }
+
+    fn height(&self) -> Length {
+        self.height
+    }
+
+    fn layout(&self, _renderer: &R, limits: &layout::Limits) -> layout::Node {
+        let limits = limits.width(self.width).height(self.height);
+        let size = limits.resolve(Size::ZERO);
+
+        layout::Node::new(size)
+    }
+
+    fn draw(
+        &self,
+        renderer: &mut R,
+        _style: &renderer::Style,
+        layout: Layout<'_>,
+        _cursor_position: Point,
+        _viewport: &Rectangle,
+    ) {
+        let bounds = layout.bounds();
+        fill_rectangle(renderer, bounds, BACKGROUND_COLOR);
+
+        let level_y = |level_db: f32| {
+            let t = ((level_db - FLOOR_DB) / (CEILING_DB - FLOOR_DB)).clamp(0.0, 1.0);
+            bounds.y + bounds.height * (1.0 - t)
+        };
+
+        for level_db in GRID_LEVELS_DB {
+            let line = Rectangle {
+                x: bounds.x,
+                y: level_y(level_db),
+                width: bounds.width,
+                height: 1.0,
+            };
+            fill_rectangle(renderer, line, GRID_COLOR);
+        }
+        for frequency in GRID_FREQUENCIES {
+            let t = (frequency / MIN_FREQUENCY).ln() / (MAX_FREQUENCY / MIN_FREQUENCY).ln();
+            let line = Rectangle {
+                x: (bounds.x + bounds.width * t).min(bounds.x + bounds.width - 1.0),
+                y: bounds.y,
+                width: 1.0,
+                height: bounds.height,
+            };
+            fill_rectangle(renderer, line, GRID_COLOR);
+        }
+
+        // Every point on the frequency axis gets a column of equal width
+        let column_width = bounds.width / self.output_db.len().max(1) as f32;
+        for (point_idx, (input_db, output_db)) in
+            self.input_db.iter().zip(self.output_db).enumerate()
+        {
+            let x = bounds.x + point_idx as f32 * column_width;
+
+            let output_y = level_y(*output_db);
+            let output_area = Rectangle {
+                x,
+                y: output_y,
+                width: column_width,
+                height: bounds.y + bounds.height - output_y,
+            };
+            fill_rectangle(renderer, output_area, OUTPUT_COLOR);
+
+            let input_line = Rectangle {
+                x,
+                y: level_y(*input_db) - 1.0,
+                width: column_width,
+                height: 2.0,
+            };
+            fill_rectangle(renderer, input_line, INPUT_COLOR);
+        }
+    }
+}
+
+impl<'a, Message> From<SpectrumView<'a>> for Element<'a, Message> {
+    fn from(widget: SpectrumView<'a>) -> Self {
+        Element::new(widget)
+    }
+}
@@ -0,0 +1,108 @@
+use nih_plug_iced::{
+    layout, renderer, Color, Element, Layout, Length, Point, Rectangle, Size, Widget,
+};
+
+use super::fill_rectangle;
+
+/// The bar spans this many cents to either side of the note.
+const RANGE_CENTS: f32 = 50.0;
+/// Within this many cents the note counts as in tune, and the marker turns green.
+const IN_TUNE_CENTS: f32 = 3.0;
+const MARKER_WIDTH: f32 = 4.0;
+
+const BACKGROUND_COLOR: Color = Color::from_rgb(0.08, 0.08, 0.08);
+const TICK_COLOR: Color = Color::from_rgb(0.25, 0.25, 0.25);
+const CENTER_COLOR: Color = Color::from_rgb(0.6, 0.6, 0.6);
+const IN_TUNE_COLOR: Color = Color::from_rgb(0.2, 0.8, 0.3);
+const OUT_OF_TUNE_COLOR: Color = Color::from_rgb(1.0, 0.45, 0.1);
+
+/// A horizontal needle showing how far the detected pitch is from the nearest note, with ticks
+/// every 10 cents. Flat is to the left and sharp is to the right.
+pub struct CentsBar {
+    cents: Option<f32>,
+
+    width: Length,
+    height: Length,
+}
+
+impl CentsBar {
+    /// The needle is hidden when `cents` is `None`.
+    pub fn new(cents: Option<f32>) -> Self {
+        Self {
+            cents,
+
+            width: Length::Units(200),
+            height: Length::Units(16),
+        }
+    }
+}
+
+impl<Message, R> Widget<Message, R> for CentsBar
+where
+    R: renderer::Renderer,
+{
+    fn width(&self) -> Length {
+        self.width
+    }
+
+    fn height(&self) -> Length {
+        self.height
+    }
+
+    fn layout(&self, _renderer: &R, limits: &layout::Limits) -> layout::Node {
+        let limits = limits.width(self.width).height(self.height);
+        let size = limits.resolve(Size::ZERO);
+
+        layout::Node::new(size)
+    }
+
+    fn draw(
+        &self,
+        renderer: &mut R,
+        _style: &renderer::Style,
+        layout: Layout<'_>,
+        _cursor_position: Point,
+        _viewport: &Rectangle,
+    ) {
+        let bounds = layout.bounds();
+        fill_rectangle(renderer, bounds, BACKGROUND_COLOR);
+
+        let cents_x = |cents: f32| {
+            bounds.x + (cents / RANGE_CENTS + 1.0) / 2.0 * (bounds.width - MARKER_WIDTH)
+        };
+        for tick in -4..=4 {
+            let (color, height) = if tick == 0 {
+                (CENTER_COLOR, bounds.height)
+            } else {
+                (TICK_COLOR, bounds.height / 2.0)
+            };
+            let tick = Rectangle {
+                x: cents_x(tick as f32 * 10.0) + MARKER_WIDTH / 2.0,
+                y: bounds.y + (bounds.height - height) / 2.0,
+                width: 1.0,
+                height,
+            };
+            fill_rectangle(renderer, tick, color);
+        }
+
+        if let Some(cents) = self.cents {
+            let color = if cents.abs() <= IN_TUNE_CENTS {
+                IN_TUNE_COLOR
+            } else {
+                OUT_OF_TUNE_COLOR
+            };
+            let marker = Rectangle {
+                x: cents_x(cents.clamp(-RANGE_CENTS, RANGE_CENTS)),
+                width: MARKER_WIDTH,
+                ..bounds
+            };
+            fill_rectangle(renderer, marker, color);
+        }
+    }
+}
+
+impl<'a, Message> From<CentsBar> for Element<'a, Message> {
+    fn from(widget: CentsBar) -> Self {
+        Element::new(widget)
+    }
+}
@@ -1,35 +1,170 @@
 use std::num::NonZeroU32;
-use atomic_float::AtomicF32;
 use nih_plug::prelude::*;
 use nih_plug_iced::IcedState;
 use std::sync::Arc;
 
+use dsp::auto_gain::AutoGain;
+use dsp::compressor::{Compressor, CompressorCurve, CompressorParams};
+use dsp::crossover::{Crossover, CrossoverParams, MAX_BANDS};
+use dsp::ducker::{Ducker, DuckerParams};
+use dsp::enhancer::Enhancer;
+use dsp::equalizer::{EqParams, Equalizer};
+use dsp::gate::{Gate, GateLevels, GateParams};
+use dsp::limiter::{Limiter, LimiterParams};
+use dsp::oversampling::{OversamplingFactor, Oversampler};
+use dsp::saturation::{SaturationAmounts, SaturationParams, Saturator};
+use dsp::stereo::{self, BassMonoizer, MidSideMode, StereoParams};
+use dsp::subharmonic::{SubHarmonicSynth, SubOctave};
+use dsp::transient::{TransientBand, TransientParams, TransientShaper};
+use meters::correlation::CorrelationMeter;
+use meters::gain_reduction::{DynamicsStage, GainReductionMeter};
+use meters::levels::LevelMeter;
+use meters::loudness::LoudnessMeter;
+use meters::{MeterSenders, Meters};
+
+mod dsp;
 mod editor;
+mod meters;
+mod presets;
 #[cfg(feature = "svg")]
 pub mod svg;
 
-const PEAK_METER_DECAY_MS: f64 = 150.0;
+/// The largest channel count in [`Basic::AUDIO_IO_LAYOUTS`], used to size per-frame scratch space.
+const MAX_CHANNELS: usize = 2;
 
 pub struct Basic {
     parameters: Arc<BasicParameters>,
-    peak_meter: Arc<AtomicF32>,
-    peak_meter_decay_weight: f32,
+    /// The meter readings and audio for the editor.
+    meters: Arc<Meters>,
+    /// The audio thread's ends of the single writer channels in `meters`.
+    meter_senders: MeterSenders,
     editor_state: Arc<IcedState>,
+
+    sample_rate: f32,
+    gate: Gate,
+    crossover: Crossover,
+    compressor: Compressor,
+    transient_shaper: TransientShaper,
+    enhancer: Enhancer,
+    sub_harmonics: SubHarmonicSynth,
+    saturator: Saturator,
+    oversampler: Oversampler,
+    equalizer: Equalizer,
+    bass_monoizer: BassMonoizer,
+    ducker: Ducker,
+    limiter: Limiter,
+    auto_gain: AutoGain,
+    input_level_meter: LevelMeter,
+    /// Unlike the input's, the output's level meter keeps running while the editor is closed so
+    /// the held maximum true peak covers everything that was played.
+    output_level_meter: LevelMeter,
+    correlation_meter: CorrelationMeter,
+    gain_reduction_meter: GainReductionMeter,
+    /// Unlike the level meters, the loudness meter keeps running while the editor is closed so
+    /// the integrated loudness covers everything that was played.
+    loudness_meter: LoudnessMeter,
+    /// Smoothed saturation parameter values for the current block. These are allocated in
+    /// `initialize()` since the saturation stage runs on whole blocks.
+    saturation_drive: Vec<f32>,
+    saturation_bias: Vec<f32>,
+    saturation_trim: Vec<f32>,
+    saturation_mix: Vec<f32>,
 }
 
 #[derive(Params)]
 pub struct BasicParameters {
     #[id = "gain"]
     pub gain: FloatParam,
+    /// Match the output's loudness to the input's, so level differences don't skew A/B
+    /// comparisons in the host.
+    #[id = "auto_gain"]
+    pub auto_gain: BoolParam,
+
+    /// How far up the harmonic series the bass enhancer reaches. The enhancer generates harmonics
+    /// for the band split's low band.
+    #[id = "harmonics"]
+    pub harmonics: FloatParam,
+    /// The level of the generated harmonics mixed back in with the dry signal. This is zero by
+    /// default, so the enhancer leaves the signal untouched until it's dialed in.
+    #[id = "blend"]
+    pub blend: FloatParam,
+
+    /// The level of the synthesized sub-harmonics.
+    #[id = "sub_level"]
+    pub sub_level: FloatParam,
+    /// Whether the sub-harmonics sit one octave, two octaves, or both below the input.
+    #[id = "sub_octave"]
+    pub sub_octave: EnumParam<SubOctave>,
+    /// The cutoff frequency of the low-pass filter that rounds off the sub-harmonics.
+    #[id = "sub_tone"]
+    pub sub_tone: FloatParam,
+    /// The center of the input band the sub-harmonic synth tracks.
+    #[id = "sub_band"]
+    pub sub_band: FloatParam,
+
+    /// Cleans up the input before anything else.
+    #[nested(group = "Gate")]
+    pub gate: GateParams,
+
+    /// Splits the signal into bands for the band specific processing.
+    #[nested(group = "Band Split")]
+    pub band_split: CrossoverParams,
+    /// Evens out the lowest band from the band split.
+    #[nested(group = "Low Band Compressor")]
+    pub compressor: CompressorParams,
+    #[nested(group = "Transient Shaper")]
+    pub transient: TransientParams,
+    #[nested(group = "Saturation")]
+    pub saturation: SaturationParams,
+    /// The oversampling factor for the saturation stage.
+    #[id = "oversampling"]
+    pub oversampling: EnumParam<OversamplingFactor>,
+    /// Tone shaping after the output gain.
+    #[nested(group = "EQ")]
+    pub eq: EqParams,
+    #[nested(group = "Stereo")]
+    pub stereo: StereoParams,
+    /// Ducks the signal when the sidechain input, usually the kick, hits.
+    #[nested(group = "Sidechain Ducking")]
+    pub ducker: DuckerParams,
+    /// The final stage, which keeps the output below the ceiling.
+    #[nested(group = "Limiter")]
+    pub limiter: LimiterParams,
 }
 
 impl Default for Basic {
     fn default() -> Self {
+        let (meter_senders, meters) = Meters::new();
+
         Self {
             parameters: Arc::new(BasicParameters::default()),
-            peak_meter: Arc::new(AtomicF32::new(0.0)),
-            peak_meter_decay_weight: 0.0,
+            meters: Arc::new(meters),
+            meter_senders,
             editor_state: editor::default_state(),
+
+            sample_rate: 1.0,
+            gate: Gate::default(),
+            crossover: Crossover::default(),
+            compressor: Compressor::default(),
+            transient_shaper: TransientShaper::default(),
+            enhancer: Enhancer::default(),
+            sub_harmonics: SubHarmonicSynth::default(),
+            saturator: Saturator::default(),
+            oversampler: Oversampler::default(),
+            equalizer: Equalizer::default(),
+            bass_monoizer: BassMonoizer::default(),
+            ducker: Ducker::default(),
+            limiter: Limiter::default(),
+            auto_gain: AutoGain::default(),
+            input_level_meter: LevelMeter::default(),
+            output_level_meter: LevelMeter::default(),
+            correlation_meter: CorrelationMeter::default(),
+            gain_reduction_meter: GainReductionMeter::default(),
+            loudness_meter: LoudnessMeter::default(),
+            saturation_drive: Vec::new(),
+            saturation_bias: Vec::new(),
+            saturation_trim: Vec::new(),
+            saturation_mix: Vec::new(),
         }
     }
 }
@@ -38,6 +173,60 @@ impl Default for BasicParameters {
     fn default() -> Self {
         Self {
             gain: FloatParam::new("Gain", 1.0, FloatRange::Linear { min: 0.0, max: 1.0 }),
+            auto_gain: BoolParam::new("Auto Gain", false),
+
+            harmonics: FloatParam::new("Harmonics", 0.5, FloatRange::Linear { min: 0.0, max: 1.0 })
+                .with_smoother(SmoothingStyle::Linear(20.0))
+                .with_unit("%")
+                .with_value_to_string(formatters::v2s_f32_percentage(0))
+                .with_string_to_value(formatters::s2v_f32_percentage()),
+            blend: FloatParam::new("Blend", 0.0, FloatRange::Linear { min: 0.0, max: 1.0 })
+                .with_smoother(SmoothingStyle::Linear(20.0))
+                .with_unit("%")
+                .with_value_to_string(formatters::v2s_f32_percentage(0))
+                .with_string_to_value(formatters::s2v_f32_percentage()),
+
+            sub_level: FloatParam::new("Sub Level", 0.0, FloatRange::Linear { min: 0.0, max: 1.0 })
+                .with_smoother(SmoothingStyle::Linear(20.0))
+                .with_unit("%")
+                .with_value_to_string(formatters::v2s_f32_percentage(0))
+                .with_string_to_value(formatters::s2v_f32_percentage()),
+            sub_octave: EnumParam::new("Sub Octave", SubOctave::One),
+            sub_tone: FloatParam::new(
+                "Sub Tone",
+                120.0,
+                FloatRange::Skewed {
+                    min: 40.0,
+                    max: 400.0,
+                    factor: FloatRange::skew_factor(-1.0),
+                },
+            )
+            .with_smoother(SmoothingStyle::Logarithmic(50.0))
+            .with_value_to_string(formatters::v2s_f32_hz_then_khz(0))
+            .with_string_to_value(formatters::s2v_f32_hz_then_khz()),
+            sub_band: FloatParam::new(
+                "Sub Band",
+                80.0,
+                FloatRange::Skewed {
+                    min: 30.0,
+                    max: 250.0,
+                    factor: FloatRange::skew_factor(-1.0),
+                },
+            )
+            .with_smoother(SmoothingStyle::Logarithmic(50.0))
+            .with_value_to_string(formatters::v2s_f32_hz_then_khz(0))
+            .with_string_to_value(formatters::s2v_f32_hz_then_khz()),
+
+            gate: GateParams::default(),
+            band_split: CrossoverParams::default(),
+            compressor: CompressorParams::default(),
+            transient: TransientParams::default(),
+            saturation: SaturationParams::default(),
+            oversampling: EnumParam::new("Oversampling", OversamplingFactor::X2),
+            eq: EqParams::default(),
+            stereo: StereoParams::default(),
+            ducker: DuckerParams::default(),
+            limiter: LimiterParams::default(),
         }
     }
 }
@@ -52,11 +241,21 @@ impl Plugin for Basic {
         AudioIOLayout {
             main_input_channels: NonZeroU32::new(2),
             main_output_channels: NonZeroU32::new(2),
+            aux_input_ports: &[new_nonzero_u32(2)],
+            names: PortNames {
+                aux_inputs: &["Sidechain"],
+                ..PortNames::const_default()
+            },
             ..AudioIOLayout::const_default()
         },
         AudioIOLayout {
             main_input_channels: NonZeroU32::new(1),
             main_output_channels: NonZeroU32::new(1),
+            aux_input_ports: &[new_nonzero_u32(1)],
+            names: PortNames {
+                aux_inputs: &["Sidechain"],
+                ..PortNames::const_default()
+            },
             ..AudioIOLayout::const_default()
         }
     ];
@@ -75,15 +274,93 @@ impl Plugin for Basic {
 
     fn initialize(
         &mut self,
-        _audio_io_layout: &AudioIOLayout,
+        audio_io_layout: &AudioIOLayout,
         buffer_config: &BufferConfig,
-        _context: &mut impl InitContext<Self>,
+        context: &mut impl InitContext<Self>,
     ) -> bool {
-        // After `PEAK_METER_DECAY_MS` milliseconds of pure silence, the peak meter's value should
-        // have dropped by 12 dB
-        self.peak_meter_decay_weight = 0.25f64
-            .powf((buffer_config.sample_rate as f64 * PEAK_METER_DECAY_MS / 1000.0).recip())
-            as f32;
+        let num_channels = audio_io_layout
+            .main_output_channels
+            .map(NonZeroU32::get)
+            .unwrap_or(0) as usize;
+        let max_buffer_size = buffer_config.max_buffer_size as usize;
+        self.sample_rate = buffer_config.sample_rate;
+
+        self.gate.initialize(num_channels, buffer_config.sample_rate);
+        self.gate.set_times(
+            self.parameters.gate.attack.value(),
+            self.parameters.gate.hold.value(),
+            self.parameters.gate.release.value(),
+        );
+        self.gate.set_key_filter(
+            self.parameters.gate.key_filter.value(),
+            self.parameters.gate.key_frequency.value(),
+        );
+        self.crossover.initialize(num_channels, buffer_config.sample_rate);
+        self.crossover.configure(
+            self.parameters.band_split.num_bands.value().count(),
+            self.parameters.band_split.slope.value(),
+            self.parameters.band_split.frequencies(),
+        );
+        self.compressor.initialize(buffer_config.sample_rate);
+        self.compressor.set_times(
+            self.parameters.compressor.attack.value(),
+            self.parameters.compressor.release.value(),
+        );
+        self.transient_shaper.initialize(buffer_config.sample_rate);
+        self.enhancer.initialize(num_channels, buffer_config.sample_rate);
+        self.enhancer.set_crossover_frequency(self.parameters.band_split.frequency_1.value());
+        self.sub_harmonics.initialize(num_channels, buffer_config.sample_rate);
+        self.sub_harmonics.set_frequencies(
+            self.parameters.sub_band.value(),
+            self.parameters.sub_tone.value(),
+        );
+
+        // Everything the oversampled saturation stage needs is allocated up front, for the
+        // highest oversampling factor
+        self.oversampler.initialize(num_channels, max_buffer_size);
+        self.oversampler.set_factor(self.parameters.oversampling.value());
+        self.saturator.initialize(num_channels);
+        self.saturator
+            .set_sample_rate(self.sample_rate * (1 << self.oversampler.num_stages()) as f32);
+        self.saturation_drive.resize(max_buffer_size, 0.0);
+        self.saturation_bias.resize(max_buffer_size, 0.0);
+        self.saturation_trim.resize(max_buffer_size, 0.0);
+        self.saturation_mix.resize(max_buffer_size, 0.0);
+
+        self.equalizer.initialize(num_channels, buffer_config.sample_rate);
+        self.equalizer.configure(self.parameters.eq.settings());
+        self.bass_monoizer.initialize(num_channels, buffer_config.sample_rate);
+        self.bass_monoizer.configure(
+            self.parameters.stereo.mono_bass.value(),
+            self.parameters.stereo.mono_frequency.value(),
+        );
+        self.ducker.initialize(buffer_config.sample_rate);
+        self.ducker.set_times(
+            self.parameters.ducker.attack.value(),
+            self.parameters.ducker.hold.value(),
+            self.parameters.ducker.release.value(),
+        );
+        self.limiter.initialize(num_channels, buffer_config.sample_rate);
+        self.limiter.set_lookahead(self.parameters.limiter.lookahead.value());
+        self.limiter.set_true_peak(self.parameters.limiter.true_peak.value());
+        self.limiter.set_release(self.parameters.limiter.release.value());
+        self.auto_gain.initialize(num_channels, buffer_config.sample_rate);
+        self.input_level_meter
+            .initialize(num_channels, buffer_config.sample_rate);
+        self.output_level_meter
+            .initialize(num_channels, buffer_config.sample_rate);
+        self.correlation_meter.initialize(buffer_config.sample_rate);
+        self.loudness_meter
+            .initialize(num_channels, buffer_config.sample_rate);
+        self.meters
+            .spectrum
+            .set_sample_rate(buffer_config.sample_rate);
+        self.meters
+            .oscilloscope
+            .initialize(num_channels, buffer_config.sample_rate);
+        self.meters.tuner.set_sample_rate(buffer_config.sample_rate);
+
+        context.set_latency_samples(self.latency());
 
         true
     }
@@ -91,35 +368,54 @@ impl Plugin for Basic {
     fn process(
         &mut self,
         buffer: &mut Buffer,
-        _aux: &mut AuxiliaryBuffers,
-        _context: &mut impl ProcessContext<Self>,
+        aux: &mut AuxiliaryBuffers,
+        context: &mut impl ProcessContext<Self>,
     ) -> ProcessStatus {
-        for channel_samples in buffer.iter_samples() {
-            let mut amplitude = 0.0;
-            let num_samples = channel_samples.len();
-
-            let gain = self.parameters.gain.smoothed.next();
-            for sample in channel_samples {
-                *sample *= gain;
-                amplitude += *sample;
-            }
+        if self.meters.loudness.take_reset_request() {
+            self.loudness_meter.reset();
+        }
+        if self.meters.output_levels.take_max_reset_request() {
+            self.output_level_meter.reset_max();
+        }
 
-            // To save resources, a plugin can (and probably should!) only perform expensive
-            // calculations that are only displayed on the GUI while the GUI is open
-            if self.editor_state.is_open() {
-                amplitude = (amplitude / num_samples as f32).abs();
-                let current_peak_meter = self.peak_meter.load(std::sync::atomic::Ordering::Relaxed);
-                let new_peak_meter = if amplitude > current_peak_meter {
-                    amplitude
-                } else {
-                    current_peak_meter * self.peak_meter_decay_weight
-                        + amplitude * (1.0 - self.peak_meter_decay_weight)
-                };
-
-                self.peak_meter
-                    .store(new_peak_meter, std::sync::atomic::Ordering::Relaxed);
-            }
+        let oversampling_changed = self
+            .oversampler
+            .set_factor(self.parameters.oversampling.value());
+        if oversampling_changed {
+            self.saturator
+                .set_sample_rate(self.sample_rate * (1 << self.oversampler.num_stages()) as f32);
         }
+        let lookahead_changed = self
+            .limiter
+            .set_lookahead(self.parameters.limiter.lookahead.value());
+        let true_peak_changed = self
+            .limiter
+            .set_true_peak(self.parameters.limiter.true_peak.value());
+        if oversampling_changed || lookahead_changed || true_peak_changed {
+            context.set_latency_samples(self.latency());
+        }
+
+        // Mid/side processing needs exactly two channels, so it turns itself off for mono audio
+        let mid_side_mode = if buffer.channels() == 2 {
+            self.parameters.stereo.mid_side.value()
+        } else {
+            MidSideMode::Off
+        };
+
+        let bypassed_channel = mid_side_mode.bypassed_channel();
+
+        self.measure_input(buffer);
+        self.process_gate(buffer);
+        self.encode_mid_side(buffer, mid_side_mode);
+        self.process_bass(buffer, bypassed_channel);
+        self.process_saturation(buffer, bypassed_channel);
+        self.decode_mid_side(buffer, mid_side_mode);
+        let sidechain = aux.inputs.first().map(Buffer::as_slice_immutable);
+        self.process_output(buffer, sidechain);
+
+        // This is only a handful of stores, and keeps the readings from going stale while the
+        // editor is closed
+        self.gain_reduction_meter.publish(&self.meters.gain_reduction);
 
         ProcessStatus::Normal
     }
@@ -131,18 +427,363 @@ impl Plugin for Basic {
     fn editor(&mut self, _async_executor: AsyncExecutor<Self>) -> Option<Box<dyn Editor>> {
         editor::create(
             self.parameters.clone(),
-            self.peak_meter.clone(),
+            self.meters.clone(),
             self.editor_state.clone(),
         )
     }
 
     fn filter_state(_state: &mut PluginState) {}
 
-    fn reset(&mut self) {}
+    fn reset(&mut self) {
+        self.gate.reset();
+        self.crossover.reset();
+        self.compressor.reset();
+        self.transient_shaper.reset();
+        self.enhancer.reset();
+        self.sub_harmonics.reset();
+        self.saturator.reset();
+        self.oversampler.reset();
+        self.equalizer.reset();
+        self.bass_monoizer.reset();
+        self.ducker.reset();
+        self.limiter.reset();
+        self.auto_gain.reset();
+        self.input_level_meter.reset();
+        self.output_level_meter.reset();
+        self.correlation_meter.reset();
+        self.gain_reduction_meter.reset();
+        self.loudness_meter.reset();
+    }
 
     fn deactivate(&mut self) {}
 }
 
+impl Basic {
+    /// Measure the unprocessed input for the auto gain, and for the input meters, the spectrum
+    /// analyzer, and the tuner while the editor is open. The spectrum analyzer shows the input and
+    /// the output on top of each other.
+    fn measure_input(&mut self, buffer: &Buffer) {
+        let editor_open = self.editor_state.is_open();
+        let channels = buffer.as_slice_immutable();
+        let mut frame = [0.0; MAX_CHANNELS];
+        for sample_idx in 0..buffer.samples() {
+            for (channel_idx, channel) in channels.iter().enumerate() {
+                frame[channel_idx] = channel[sample_idx];
+            }
+
+            let input = &frame[..channels.len()];
+            self.auto_gain.measure_input(input);
+            if editor_open {
+                self.input_level_meter.process(input);
+                self.meter_senders.spectrum.push_input(input);
+                self.meters.tuner.push(input);
+            }
+        }
+
+        if editor_open {
+            self.input_level_meter.publish(&self.meters.input_levels);
+        }
+    }
+
+    /// The total latency of the oversampling filters and the limiter.
+    fn latency(&self) -> u32 {
+        self.oversampler.latency() + self.limiter.latency()
+    }
+
+    /// Run the gate on the input, before the enhancement can bring up the noise between notes.
+    fn process_gate(&mut self, buffer: &mut Buffer) {
+        for mut channel_samples in buffer.iter_samples() {
+            self.gate.set_times(
+                self.parameters.gate.attack.value(),
+                self.parameters.gate.hold.value(),
+                self.parameters.gate.release.value(),
+            );
+            self.gate.set_key_filter(
+                self.parameters.gate.key_filter.value(),
+                self.parameters.gate.key_frequency.smoothed.next(),
+            );
+            let levels = GateLevels {
+                threshold_db: self.parameters.gate.threshold.smoothed.next(),
+                range_db: self.parameters.gate.range.smoothed.next(),
+                hysteresis_db: self.parameters.gate.hysteresis.value(),
+            };
+
+            let mut key_peak = 0.0f32;
+            for (channel_idx, sample) in channel_samples.iter_mut().enumerate() {
+                key_peak = key_peak.max(self.gate.key(channel_idx, *sample));
+            }
+
+            let gain = self.gate.next_gain(key_peak, levels);
+            self.gain_reduction_meter.record(DynamicsStage::Gate, gain);
+            for sample in channel_samples {
+                *sample *= gain;
+            }
+        }
+    }
+
+    /// Convert a stereo buffer to mid/side for the processing chain. Does nothing when `mode` is
+    /// [`MidSideMode::Off`].
+    fn encode_mid_side(&mut self, buffer: &mut Buffer, mode: MidSideMode) {
+        let [left, right] = buffer.as_slice() else {
+            return;
+        };
+        if mode == MidSideMode::Off {
+            return;
+        }
+
+        for (left, right) in left.iter_mut().zip(right.iter_mut()) {
+            (*left, *right) = stereo::encode_mid_side(*left, *right);
+        }
+    }
+
+    /// Convert back to left/right and apply the stereo width. The width is also applied when
+    /// mid/side processing is off.
+    fn decode_mid_side(&mut self, buffer: &mut Buffer, mode: MidSideMode) {
+        let [left, right] = buffer.as_slice() else {
+            return;
+        };
+
+        for (left, right) in left.iter_mut().zip(right.iter_mut()) {
+            let width = self.parameters.stereo.width.smoothed.next();
+            let (mid, side) = if mode == MidSideMode::Off {
+                stereo::encode_mid_side(*left, *right)
+            } else {
+                (*left, *right)
+            };
+
+            (*left, *right) = stereo::decode_mid_side(mid, side, width);
+        }
+    }
+
+    /// Run the band split, the low band compressor, the transient shaper, the sub-harmonic synth,
+    /// and the enhancer. The bypassed channel in the mid only and side only modes is still split
+    /// and summed back together, so it gets the same all-pass phase response as the processed
+    /// channel.
+    fn process_bass(&mut self, buffer: &mut Buffer, bypassed_channel: Option<usize>) {
+        for mut channel_samples in buffer.iter_samples() {
+            let crossover_frequencies = self.parameters.band_split.next_frequencies();
+            self.crossover.configure(
+                self.parameters.band_split.num_bands.value().count(),
+                self.parameters.band_split.slope.value(),
+                crossover_frequencies,
+            );
+            let compressor_curve = CompressorCurve {
+                threshold_db: self.parameters.compressor.threshold.smoothed.next(),
+                ratio: self.parameters.compressor.ratio.smoothed.next(),
+                knee_db: self.parameters.compressor.knee.smoothed.next(),
+                makeup_db: self.parameters.compressor.makeup.smoothed.next(),
+            };
+            self.compressor.set_times(
+                self.parameters.compressor.attack.value(),
+                self.parameters.compressor.release.value(),
+            );
+            let transient_attack = self.parameters.transient.attack.smoothed.next();
+            let transient_sustain = self.parameters.transient.sustain.smoothed.next();
+            let transient_band = self.parameters.transient.band.value();
+            let harmonics = self.parameters.harmonics.smoothed.next();
+            let blend = self.parameters.blend.smoothed.next();
+            self.enhancer.set_crossover_frequency(crossover_frequencies[0]);
+            let sub_level = self.parameters.sub_level.smoothed.next();
+            let sub_octave = self.parameters.sub_octave.value();
+            self.sub_harmonics.set_frequencies(
+                self.parameters.sub_band.smoothed.next(),
+                self.parameters.sub_tone.smoothed.next(),
+            );
+
+            // The compressor's and the transient shaper's detectors are linked, so all channels
+            // need to be split first. The bypassed channel doesn't drive the detectors.
+            let mut bands = [[0.0; MAX_BANDS]; MAX_CHANNELS];
+            let mut input_peak = 0.0f32;
+            let mut low_band_peak = 0.0f32;
+            for (channel_idx, sample) in channel_samples.iter_mut().enumerate() {
+                bands[channel_idx] = self.crossover.split(channel_idx, *sample);
+                if Some(channel_idx) != bypassed_channel {
+                    input_peak = input_peak.max(sample.abs());
+                    low_band_peak = low_band_peak.max(bands[channel_idx][0].abs());
+                }
+            }
+            let mut low_band_gain = self.compressor.next_gain(low_band_peak, compressor_curve);
+            self.gain_reduction_meter
+                .record(DynamicsStage::Compressor, self.compressor.gain_reduction());
+
+            // The transient shaper comes after the compressor so the compressor can't undo it
+            let transient_sidechain = match transient_band {
+                TransientBand::Full => input_peak,
+                TransientBand::Low => low_band_peak,
+            };
+            let transient_gain = self.transient_shaper.next_gain(
+                transient_sidechain,
+                transient_attack,
+                transient_sustain,
+            );
+            let full_band_gain = match transient_band {
+                TransientBand::Full => transient_gain,
+                TransientBand::Low => {
+                    low_band_gain *= transient_gain;
+                    1.0
+                }
+            };
+
+            for (channel_idx, sample) in channel_samples.into_iter().enumerate() {
+                let channel_bands = &mut bands[channel_idx];
+                if Some(channel_idx) == bypassed_channel {
+                    *sample = channel_bands.iter().sum();
+                    continue;
+                }
+
+                channel_bands[0] *= low_band_gain;
+                *sample = channel_bands.iter().sum::<f32>() * full_band_gain;
+
+                // The sub-harmonics track the compressed and transient shaped signal, and are
+                // kept out of the enhancer
+                let sub = self
+                    .sub_harmonics
+                    .process(channel_idx, *sample, sub_octave, sub_level);
+                let low = channel_bands[0] * full_band_gain;
+                *sample = self.enhancer.process(channel_idx, *sample, low, harmonics, blend) + sub;
+            }
+        }
+    }
+
+    /// Run the saturation stage inside of the oversampler. The smoothed parameter values are
+    /// computed for the whole block up front, and every oversampled sample uses the value of the
+    /// frame it was upsampled from. The bypassed channel only goes through the oversampling
+    /// filters and the saturator's DC blocker, so it gets the same latency and phase response as
+    /// the saturated channel.
+    fn process_saturation(&mut self, buffer: &mut Buffer, bypassed_channel: Option<usize>) {
+        let num_samples = buffer.samples();
+        let saturation = &self.parameters.saturation;
+        let curve = saturation.curve.value();
+        saturation
+            .drive
+            .smoothed
+            .next_block(&mut self.saturation_drive, num_samples);
+        saturation
+            .bias
+            .smoothed
+            .next_block(&mut self.saturation_bias, num_samples);
+        saturation
+            .output_trim
+            .smoothed
+            .next_block(&mut self.saturation_trim, num_samples);
+        saturation
+            .mix
+            .smoothed
+            .next_block(&mut self.saturation_mix, num_samples);
+        // With the mix all the way down the saturator is skipped entirely
+        let saturation_active = self.saturation_mix[..num_samples]
+            .iter()
+            .any(|mix| *mix > 0.0);
+
+        let oversampling_stages = self.oversampler.num_stages();
+        for (channel_idx, channel_samples) in buffer.as_slice().iter_mut().enumerate() {
+            let saturator = &mut self.saturator;
+            let drive = &self.saturation_drive;
+            let bias = &self.saturation_bias;
+            let trim = &self.saturation_trim;
+            let mix = &self.saturation_mix;
+
+            self.oversampler
+                .process(channel_idx, channel_samples, |oversampled| {
+                    if !saturation_active {
+                        return;
+                    }
+                    let bypassed = Some(channel_idx) == bypassed_channel;
+
+                    for (sample_idx, sample) in oversampled.iter_mut().enumerate() {
+                        let frame_idx = sample_idx >> oversampling_stages;
+                        let amounts = SaturationAmounts {
+                            drive: drive[frame_idx],
+                            bias: bias[frame_idx],
+                            output_trim: trim[frame_idx],
+                            mix: if bypassed { 0.0 } else { mix[frame_idx] },
+                        };
+                        *sample = saturator.process(channel_idx, *sample, curve, amounts);
+                    }
+                });
+        }
+    }
+
+    /// Apply the output gain, the sidechain ducking, the EQ, the bass mono-izer, the auto gain,
+    /// and the limiter, and update the output meters. The ducking is skipped when the host didn't
+    /// provide a sidechain buffer.
+    fn process_output(&mut self, buffer: &mut Buffer, sidechain: Option<&[&mut [f32]]>) {
+        let auto_gain_enabled = self.parameters.auto_gain.value();
+        for (sample_idx, mut channel_samples) in buffer.iter_samples().enumerate() {
+            let num_channels = channel_samples.len();
+
+            self.ducker.set_times(
+                self.parameters.ducker.attack.value(),
+                self.parameters.ducker.hold.value(),
+                self.parameters.ducker.release.value(),
+            );
+            let duck_threshold = self.parameters.ducker.threshold.smoothed.next();
+            let duck_depth = self.parameters.ducker.depth.smoothed.next();
+            let duck_gain = match sidechain {
+                Some(sidechain) => {
+                    let sidechain_peak = sidechain
+                        .iter()
+                        .fold(0.0f32, |peak, channel| peak.max(channel[sample_idx].abs()));
+                    self.ducker
+                        .next_gain(sidechain_peak, duck_threshold, duck_depth)
+                }
+                None => 1.0,
+            };
+
+            self.gain_reduction_meter.record(DynamicsStage::Ducker, duck_gain);
+
+            let gain = self.parameters.gain.smoothed.next() * duck_gain;
+            self.limiter.set_release(self.parameters.limiter.release.value());
+            let ceiling = util::db_to_gain(self.parameters.limiter.ceiling.smoothed.next());
+            self.equalizer.configure(self.parameters.eq.next_settings());
+            self.bass_monoizer.configure(
+                self.parameters.stereo.mono_bass.value(),
+                self.parameters.stereo.mono_frequency.smoothed.next(),
+            );
+
+            let mut frame = [0.0; MAX_CHANNELS];
+            for (channel_idx, sample) in channel_samples.iter_mut().enumerate() {
+                frame[channel_idx] = self.equalizer.process(channel_idx, *sample * gain);
+            }
+            self.bass_monoizer.process(&mut frame[..num_channels]);
+
+            // The compensation comes before the limiter, so it can't push the output past the
+            // ceiling
+            let auto_gain = self
+                .auto_gain
+                .next_gain(&frame[..num_channels], auto_gain_enabled);
+            for sample in &mut frame[..num_channels] {
+                *sample *= auto_gain;
+            }
+
+            self.limiter.process(&mut frame[..num_channels], ceiling);
+            self.gain_reduction_meter
+                .record(DynamicsStage::Limiter, self.limiter.gain_reduction());
+
+            for (sample, processed) in channel_samples.into_iter().zip(frame) {
+                *sample = processed;
+            }
+            self.loudness_meter.process(&frame[..num_channels]);
+            self.output_level_meter.process(&frame[..num_channels]);
+
+            // To save resources, a plugin can (and probably should!) only perform expensive
+            // calculations that are only displayed on the GUI while the GUI is open
+            if self.editor_state.is_open() {
+                self.correlation_meter.process(&frame[..num_channels]);
+                self.meter_senders.spectrum.push_output(&frame[..num_channels]);
+                self.meters.oscilloscope.push(&frame[..num_channels]);
+            }
+        }
+
+        if self.editor_state.is_open() {
+            self.output_level_meter.publish(&self.meters.output_levels);
+            self.correlation_meter.publish(&self.meters.output_correlation);
+            self.meters.auto_gain.set(self.auto_gain.gain());
+        }
+        self.loudness_meter.publish(&self.meters.loudness);
+    }
+}
+
 impl Vst3Plugin for Basic {
     const VST3_CLASS_ID: [u8; 16] = *b"PhatbassGain0001";
 
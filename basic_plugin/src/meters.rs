@@ -0,0 +1,84 @@
+//! Metering for the editor. The meters run on the audio thread and publish their readings as
+//! [`Reading`]s, so the editor can read them without locking. The spectrum analyzer, the
+//! oscilloscope, and the tuner are the exception, they receive the audio itself and do their
+//! analysis in the editor. The oscilloscope and the tuner get every sample through
+//! [`AudioQueue`]s, and the spectrum analyzer gets overlapping windows through [`FrameBuffer`]s,
+//! whose sending ends the audio thread owns in [`MeterSenders`]. Nothing here allocates on the
+//! audio thread.
+//!
+//! [`AudioQueue`]: self::bus::AudioQueue
+//! [`FrameBuffer`]: self::bus::FrameBuffer
+
+use self::bus::Reading;
+use self::gain_reduction::GainReduction;
+use self::levels::ChannelLevels;
+use self::loudness::LoudnessReadings;
+use self::oscilloscope::OscilloscopeBuffer;
+use self::spectrum::{SpectrumBuffers, SpectrumSender};
+use self::tuner::TunerBuffer;
+
+pub mod bus;
+pub mod correlation;
+pub mod gain_reduction;
+pub mod levels;
+pub mod loudness;
+pub mod oscilloscope;
+pub mod spectrum;
+pub mod tuner;
+
+/// Everything the audio thread hands to the editor, shared through a single `Arc`. New meters get
+/// a field here, so both sides stay typed.
+pub struct Meters {
+    /// The unprocessed input's peak and RMS levels per channel.
+    pub input_levels: ChannelLevels,
+    /// The output's peak and RMS levels per channel.
+    pub output_levels: ChannelLevels,
+    /// The phase correlation between the output's left and right channels.
+    pub output_correlation: Reading,
+    /// The output's loudness. The editor also uses this to restart the measurement.
+    pub loudness: LoudnessReadings,
+    /// The input and output audio for the spectrum analyzer.
+    pub spectrum: SpectrumBuffers,
+    /// The output audio for the oscilloscope and the goniometer.
+    pub oscilloscope: OscilloscopeBuffer,
+    /// The input audio for the tuner.
+    pub tuner: TunerBuffer,
+    /// The gain reduction of every dynamics stage.
+    pub gain_reduction: GainReduction,
+    /// The gain the auto gain applies to match the output's loudness to the input's.
+    pub auto_gain: Reading,
+}
+
+/// The audio thread's ends of the channels in [`Meters`] that only allow a single writer. These
+/// are owned by the plugin instead of being shared.
+pub struct MeterSenders {
+    pub spectrum: SpectrumSender,
+}
+
+impl Meters {
+    pub fn new() -> (MeterSenders, Self) {
+        let (spectrum_sender, spectrum) = SpectrumBuffers::new();
+
+        (
+            MeterSenders {
+                spectrum: spectrum_sender,
+            },
+            Self {
+                input_levels: ChannelLevels::default(),
+                output_levels: ChannelLevels::default(),
+                output_correlation: Reading::new(0.0),
+                loudness: LoudnessReadings::default(),
+                spectrum,
+                oscilloscope: OscilloscopeBuffer::default(),
+                tuner: TunerBuffer::default(),
+                gain_reduction: GainReduction::default(),
+                auto_gain: Reading::new(1.0),
+            },
+        )
+    }
+}
+
+/// Sum a frame to mono, for the analyzers that only look at a single channel.
+fn downmix(frame: &[f32]) -> f32 {
+    frame.iter().sum::<f32>() / frame.len().max(1) as f32
+}
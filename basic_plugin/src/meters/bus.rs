@@ -0,0 +1,193 @@
+//! The channels [`Meters`][super::Meters] is built from. All of them are lock-free, and none of
+//! them allocate after they have been created, so the audio thread can write to them freely.
+//!
+//! [`Reading`]s and [`AudioQueue`]s are written through the shared [`Meters`][super::Meters].
+//! A [`FrameBuffer`] can only have a single writer, so its writing end, the [`FrameSender`], is
+//! owned by the plugin instead.
+
+use atomic_float::AtomicF32;
+use atomic_refcell::AtomicRefCell;
+use crossbeam::queue::ArrayQueue;
+use std::cell::UnsafeCell;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+/// Set in [`TripleBuffer::back`] when the back slot holds a frame the reader hasn't seen yet.
+const NEW_FRAME: usize = 0b100;
+const SLOT_MASK: usize = 0b011;
+
+/// A single value written by the audio thread and read by the editor. The editor only ever sees
+/// the latest value.
+#[derive(Default)]
+pub struct Reading(AtomicF32);
+
+impl Reading {
+    pub fn new(value: f32) -> Self {
+        Self(AtomicF32::new(value))
+    }
+
+    pub fn get(&self) -> f32 {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    pub fn set(&self, value: f32) {
+        self.0.store(value, Ordering::Relaxed);
+    }
+}
+
+/// Carries audio from the audio thread to the editor, one item per sample or frame. The storage
+/// is allocated up front. When the editor falls behind or is closed, the oldest items are
+/// dropped instead of blocking the audio thread.
+pub struct AudioQueue<T> {
+    queue: ArrayQueue<T>,
+}
+
+impl<T> AudioQueue<T> {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            queue: ArrayQueue::new(capacity),
+        }
+    }
+
+    pub fn push(&self, item: T) {
+        self.queue.force_push(item);
+    }
+
+    pub fn pop(&self) -> Option<T> {
+        self.queue.pop()
+    }
+}
+
+/// Three slots for frames. At any time the writer owns one of them, the reader owns another, and
+/// the third one is the back slot. Publishing a frame swaps the writer's slot with the back slot,
+/// and reading a new frame swaps the reader's slot with it, so neither side ever waits.
+struct TripleBuffer<T> {
+    slots: [UnsafeCell<T>; 3],
+    /// The index of the back slot, combined with [`NEW_FRAME`].
+    back: AtomicUsize,
+}
+
+// SAFETY: A slot is only ever accessed by the side that owns it, and slots only change owners
+//         through the swaps on `back`, which also synchronize their contents
+unsafe impl<T: Send> Sync for TripleBuffer<T> {}
+
+/// The audio thread's end of a [`FrameBuffer`].
+pub struct FrameSender<T> {
+    buffer: Arc<TripleBuffer<T>>,
+    write_slot: usize,
+}
+
+/// Hands bulk frames, like a window of audio for the spectrum analyzer, from the audio thread to
+/// the editor through a triple buffer. Neither side waits for the other, and the editor always
+/// gets the most recent complete frame.
+pub struct FrameBuffer<T> {
+    buffer: Arc<TripleBuffer<T>>,
+    /// Only one reader at a time can own the read slot.
+    read_slot: AtomicRefCell<usize>,
+}
+
+/// Create a frame buffer. Every slot starts out as a copy of `initial`, which also sets the size
+/// of frames that contain a `Vec`.
+pub fn frame_buffer<T: Clone>(initial: &T) -> (FrameSender<T>, FrameBuffer<T>) {
+    let buffer = Arc::new(TripleBuffer {
+        slots: [
+            UnsafeCell::new(initial.clone()),
+            UnsafeCell::new(initial.clone()),
+            UnsafeCell::new(initial.clone()),
+        ],
+        back: AtomicUsize::new(1),
+    });
+
+    (
+        FrameSender {
+            buffer: buffer.clone(),
+            write_slot: 0,
+        },
+        FrameBuffer {
+            buffer,
+            read_slot: AtomicRefCell::new(2),
+        },
+    )
+}
+
+impl<T> FrameSender<T> {
+    /// Fill in the next frame and publish it. The frame passed to `write` holds stale data from
+    /// an older frame, so all of it needs to be overwritten.
+    pub fn publish(&mut self, write: impl FnOnce(&mut T)) {
+        // SAFETY: The write slot is only accessed through this sender
+        write(unsafe { &mut *self.buffer.slots[self.write_slot].get() });
+
+        let back = self
+            .buffer
+            .back
+            .swap(self.write_slot | NEW_FRAME, Ordering::AcqRel);
+        self.write_slot = back & SLOT_MASK;
+    }
+}
+
+impl<T> FrameBuffer<T> {
+    /// Call `read` with the newest frame if one was published since the last call. Returns
+    /// whether there was a new frame.
+    pub fn read_new(&self, read: impl FnOnce(&T)) -> bool {
+        let Ok(mut read_slot) = self.read_slot.try_borrow_mut() else {
+            return false;
+        };
+        // Only the reader clears the flag, so a new frame can't disappear before the swap
+        if self.buffer.back.load(Ordering::Relaxed) & NEW_FRAME == 0 {
+            return false;
+        }
+
+        let back = self.buffer.back.swap(*read_slot, Ordering::AcqRel);
+        *read_slot = back & SLOT_MASK;
+        // SAFETY: The read slot is only accessed while `read_slot` is borrowed
+        read(unsafe { &*self.buffer.slots[*read_slot].get() });
+
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frame_buffer_hands_over_the_newest_frame() {
+        let (mut sender, buffer) = frame_buffer(&0);
+        assert!(!buffer.read_new(|_| panic!("nothing was published")));
+
+        sender.publish(|frame| *frame = 1);
+        sender.publish(|frame| *frame = 2);
+        let mut received = None;
+        assert!(buffer.read_new(|frame| received = Some(*frame)));
+        assert_eq!(received, Some(2));
+        assert!(!buffer.read_new(|_| panic!("no new frame was published")));
+
+        // Publishing keeps cycling through the slots the reader doesn't own
+        for value in 3..10 {
+            sender.publish(|frame| *frame = value);
+            assert!(buffer.read_new(|frame| received = Some(*frame)));
+            assert_eq!(received, Some(value));
+        }
+    }
+
+    #[test]
+    fn frame_buffer_across_threads() {
+        let (mut sender, buffer) = frame_buffer(&vec![0u32; 64]);
+        let writer = std::thread::spawn(move || {
+            for value in 1..=10_000 {
+                sender.publish(|frame| frame.fill(value));
+            }
+        });
+
+        let mut last = 0;
+        while last < 10_000 {
+            buffer.read_new(|frame| {
+                // A torn frame would mix values from different publishes
+                assert!(frame.iter().all(|value| *value == frame[0]));
+                assert!(frame[0] >= last);
+                last = frame[0];
+            });
+        }
+        writer.join().unwrap();
+    }
+}
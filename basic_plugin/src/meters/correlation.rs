@@ -0,0 +1,61 @@
+use super::bus::Reading;
+use crate::dsp::envelope_weight;
+
+/// The integration time, which is in the range commonly used by hardware correlation meters.
+const INTEGRATION_MS: f32 = 300.0;
+/// Below this mean square level the channels count as silent, and the meter rests in the middle.
+const SILENCE_MEAN_SQUARE: f32 = 1e-10;
+
+/// Measures the phase correlation between the left and right channels. The reading is +1 for
+/// identical channels, 0 for unrelated channels, and -1 when one channel is the inverse of the
+/// other, which would cancel out when summed to mono. A mono signal always reads +1.
+#[derive(Default)]
+pub struct CorrelationMeter {
+    weight: f32,
+
+    /// The averaged product of the two channels.
+    product: f32,
+    left_mean_square: f32,
+    right_mean_square: f32,
+}
+
+impl CorrelationMeter {
+    /// Set up the meter for a new sample rate. This must be called from `initialize()`.
+    pub fn initialize(&mut self, sample_rate: f32) {
+        self.weight = envelope_weight(sample_rate, INTEGRATION_MS);
+        self.reset();
+    }
+
+    pub fn reset(&mut self) {
+        self.product = 0.0;
+        self.left_mean_square = 0.0;
+        self.right_mean_square = 0.0;
+    }
+
+    /// Measure a single frame. Mono frames are treated as identical left and right channels.
+    pub fn process(&mut self, frame: &[f32]) {
+        let left = frame[0];
+        let right = frame.get(1).copied().unwrap_or(left);
+
+        self.product = self.product * self.weight + left * right * (1.0 - self.weight);
+        self.left_mean_square =
+            self.left_mean_square * self.weight + left * left * (1.0 - self.weight);
+        self.right_mean_square =
+            self.right_mean_square * self.weight + right * right * (1.0 - self.weight);
+    }
+
+    /// The current correlation, between -1 and +1.
+    pub fn correlation(&self) -> f32 {
+        let mean_square = (self.left_mean_square * self.right_mean_square).sqrt();
+        if mean_square < SILENCE_MEAN_SQUARE {
+            0.0
+        } else {
+            (self.product / mean_square).clamp(-1.0, 1.0)
+        }
+    }
+
+    /// Store the current reading in `correlation` for the editor.
+    pub fn publish(&self, correlation: &Reading) {
+        correlation.set(self.correlation());
+    }
+}
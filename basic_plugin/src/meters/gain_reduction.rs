@@ -0,0 +1,86 @@
+use super::bus::Reading;
+
+/// The stages that turn the signal down, and whose gain reduction is shown in the editor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DynamicsStage {
+    Gate,
+    Compressor,
+    Ducker,
+    Limiter,
+}
+
+const NUM_STAGES: usize = DynamicsStage::ALL.len();
+
+impl DynamicsStage {
+    /// All stages in processing order.
+    pub const ALL: [DynamicsStage; 4] = [
+        DynamicsStage::Gate,
+        DynamicsStage::Compressor,
+        DynamicsStage::Ducker,
+        DynamicsStage::Limiter,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            DynamicsStage::Gate => "Gate",
+            DynamicsStage::Compressor => "Comp",
+            DynamicsStage::Ducker => "Duck",
+            DynamicsStage::Limiter => "Limit",
+        }
+    }
+}
+
+/// The gain reduction of every dynamics stage as a gain factor, shared between the audio thread
+/// and the editor. A value of 1.0 means the stage isn't doing anything.
+pub struct GainReduction {
+    stages: [Reading; NUM_STAGES],
+}
+
+impl Default for GainReduction {
+    fn default() -> Self {
+        Self {
+            stages: std::array::from_fn(|_| Reading::new(1.0)),
+        }
+    }
+}
+
+impl GainReduction {
+    /// The largest gain reduction `stage` applied during the last buffer.
+    pub fn get(&self, stage: DynamicsStage) -> f32 {
+        self.stages[stage as usize].get()
+    }
+}
+
+/// Collects the gain reduction of every dynamics stage on the audio thread. The stages report
+/// their gain for every sample, and the largest reduction during a buffer gets published.
+pub struct GainReductionMeter {
+    min_gains: [f32; NUM_STAGES],
+}
+
+impl Default for GainReductionMeter {
+    fn default() -> Self {
+        Self {
+            min_gains: [1.0; NUM_STAGES],
+        }
+    }
+}
+
+impl GainReductionMeter {
+    pub fn reset(&mut self) {
+        self.min_gains = [1.0; NUM_STAGES];
+    }
+
+    /// Report the gain `stage` applies to the current sample, without any makeup gain.
+    pub fn record(&mut self, stage: DynamicsStage, gain: f32) {
+        let min_gain = &mut self.min_gains[stage as usize];
+        *min_gain = min_gain.min(gain);
+    }
+
+    /// Store the largest gain reductions since the last call in `gain_reduction` for the editor.
+    pub fn publish(&mut self, gain_reduction: &GainReduction) {
+        for (stage, min_gain) in gain_reduction.stages.iter().zip(&self.min_gains) {
+            stage.set(*min_gain);
+        }
+        self.reset();
+    }
+}
@@ -0,0 +1,124 @@
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+use super::bus::Reading;
+use crate::dsp::envelope_weight;
+use crate::dsp::true_peak::TruePeakDetector;
+use crate::MAX_CHANNELS;
+
+/// The peak meters fall by 20 dB in 1.7 seconds, like an IEC 60268-10 Type I peak programme
+/// meter.
+const PEAK_DECAY_DB_PER_SECOND: f32 = 20.0 / 1.7;
+/// The integration time for the RMS meters, which matches the response of a VU meter.
+const RMS_INTEGRATION_MS: f32 = 300.0;
+
+/// The peak and RMS levels of every channel as gain factors, shared between the audio thread and
+/// the editor.
+#[derive(Default)]
+pub struct ChannelLevels {
+    num_channels: AtomicUsize,
+    peak: [Reading; MAX_CHANNELS],
+    rms: [Reading; MAX_CHANNELS],
+    max_true_peak: Reading,
+
+    /// Set by the editor to clear the held maximum true peak.
+    max_reset_requested: AtomicBool,
+}
+
+impl ChannelLevels {
+    /// The number of channels in the current audio layout.
+    pub fn num_channels(&self) -> usize {
+        self.num_channels.load(Ordering::Relaxed)
+    }
+
+    /// The channel's decaying true-peak level.
+    pub fn peak(&self, channel_idx: usize) -> f32 {
+        self.peak[channel_idx].get()
+    }
+
+    pub fn rms(&self, channel_idx: usize) -> f32 {
+        self.rms[channel_idx].get()
+    }
+
+    /// The highest true peak of any channel since the meter was started or reset.
+    pub fn max_true_peak(&self) -> f32 {
+        self.max_true_peak.get()
+    }
+
+    /// Ask the audio thread to clear the held maximum true peak. This is picked up at the start of
+    /// the next buffer.
+    pub fn request_max_reset(&self) {
+        self.max_reset_requested.store(true, Ordering::Relaxed);
+    }
+
+    /// Returns `true` once after [`request_max_reset()`][Self::request_max_reset()] has been
+    /// called.
+    pub fn take_max_reset_request(&self) -> bool {
+        self.max_reset_requested.swap(false, Ordering::Relaxed)
+    }
+}
+
+/// Measures the true-peak and RMS levels of every channel separately, so out of phase channels
+/// can't cancel each other out. The peaks rise instantly and then fall at a constant rate in
+/// decibels, and the RMS levels are averaged over the last few hundred milliseconds. The highest
+/// true peak is also held until the meter is reset, so short overs can't be missed.
+#[derive(Default)]
+pub struct LevelMeter {
+    peak_decay_weight: f32,
+    rms_weight: f32,
+
+    true_peak: TruePeakDetector,
+    channels: Vec<ChannelState>,
+    max_true_peak: f32,
+}
+
+#[derive(Default, Clone)]
+struct ChannelState {
+    peak: f32,
+    mean_square: f32,
+}
+
+impl LevelMeter {
+    /// Allocate state for `num_channels` channels. This must be called from `initialize()`.
+    pub fn initialize(&mut self, num_channels: usize, sample_rate: f32) {
+        self.peak_decay_weight = 10.0f32.powf(-PEAK_DECAY_DB_PER_SECOND / 20.0 / sample_rate);
+        self.rms_weight = envelope_weight(sample_rate, RMS_INTEGRATION_MS);
+
+        self.true_peak.initialize(num_channels);
+        self.channels.clear();
+        self.channels.resize(num_channels, ChannelState::default());
+    }
+
+    pub fn reset(&mut self) {
+        self.true_peak.reset();
+        self.channels.fill(ChannelState::default());
+        self.max_true_peak = 0.0;
+    }
+
+    /// Clear the held maximum true peak without touching the other readings.
+    pub fn reset_max(&mut self) {
+        self.max_true_peak = 0.0;
+    }
+
+    /// Measure a single frame containing one sample for every channel.
+    pub fn process(&mut self, frame: &[f32]) {
+        for (channel_idx, (channel, sample)) in self.channels.iter_mut().zip(frame).enumerate() {
+            let true_peak = self.true_peak.process(channel_idx, *sample);
+            channel.peak = true_peak.max(channel.peak * self.peak_decay_weight);
+            self.max_true_peak = self.max_true_peak.max(true_peak);
+            channel.mean_square =
+                channel.mean_square * self.rms_weight + sample * sample * (1.0 - self.rms_weight);
+        }
+    }
+
+    /// Store the current readings in `levels` for the editor.
+    pub fn publish(&self, levels: &ChannelLevels) {
+        levels
+            .num_channels
+            .store(self.channels.len(), Ordering::Relaxed);
+        for (channel_idx, channel) in self.channels.iter().enumerate() {
+            levels.peak[channel_idx].set(channel.peak);
+            levels.rms[channel_idx].set(channel.mean_square.sqrt());
+        }
+        levels.max_true_peak.set(self.max_true_peak);
+    }
+}
@@ -0,0 +1,370 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use super::bus::Reading;
+use crate::dsp::k_weighting::KWeighting;
+
+/// The loudness is measured in 100 ms steps. The momentary and short-term windows, and the gating
+/// blocks for the integrated loudness, are all made up of these.
+const STEP_MS: f32 = 100.0;
+const MOMENTARY_STEPS: usize = 4;
+const SHORT_TERM_STEPS: usize = 30;
+
+/// Blocks quieter than this are ignored for the integrated loudness and the loudness range.
+const ABSOLUTE_GATE_LUFS: f32 = -70.0;
+/// Gating blocks more than this far below the ungated integrated loudness are ignored.
+const INTEGRATED_RELATIVE_GATE_LU: f32 = -10.0;
+/// Short-term values more than this far below their average are left out of the loudness range.
+const RANGE_RELATIVE_GATE_LU: f32 = -20.0;
+const RANGE_LOW_PERCENTILE: f32 = 0.10;
+const RANGE_HIGH_PERCENTILE: f32 = 0.95;
+
+/// The gated measurements are kept as histograms with 0.1 LU wide bins, so the memory use doesn't
+/// grow with the length of the measurement. Louder values end up in the last bin.
+const HISTOGRAM_MAX_LUFS: f32 = 5.0;
+const HISTOGRAM_BINS_PER_LU: f32 = 10.0;
+const HISTOGRAM_LEN: usize =
+    ((HISTOGRAM_MAX_LUFS - ABSOLUTE_GATE_LUFS) * HISTOGRAM_BINS_PER_LU) as usize;
+
+/// The loudness readings in LUFS, or LU for the loudness range, shared between the audio thread
+/// and the editor. Readings without enough data are negative infinity.
+pub struct LoudnessReadings {
+    momentary: Reading,
+    short_term: Reading,
+    integrated: Reading,
+    range: Reading,
+
+    /// Set by the editor to restart the integrated loudness and loudness range measurements.
+    reset_requested: AtomicBool,
+}
+
+impl Default for LoudnessReadings {
+    fn default() -> Self {
+        Self {
+            momentary: Reading::new(f32::NEG_INFINITY),
+            short_term: Reading::new(f32::NEG_INFINITY),
+            integrated: Reading::new(f32::NEG_INFINITY),
+            range: Reading::new(f32::NEG_INFINITY),
+
+            reset_requested: AtomicBool::new(false),
+        }
+    }
+}
+
+impl LoudnessReadings {
+    pub fn momentary(&self) -> f32 {
+        self.momentary.get()
+    }
+
+    pub fn short_term(&self) -> f32 {
+        self.short_term.get()
+    }
+
+    pub fn integrated(&self) -> f32 {
+        self.integrated.get()
+    }
+
+    pub fn range(&self) -> f32 {
+        self.range.get()
+    }
+
+    /// Ask the audio thread to start a new measurement. This is picked up at the start of the next
+    /// buffer.
+    pub fn request_reset(&self) {
+        self.reset_requested.store(true, Ordering::Relaxed);
+    }
+
+    /// Returns `true` once after [`request_reset()`][Self::request_reset()] has been called.
+    pub fn take_reset_request(&self) -> bool {
+        self.reset_requested.swap(false, Ordering::Relaxed)
+    }
+}
+
+/// A loudness meter following ITU-R BS.1770-4 and EBU R128. The signal is K-weighted, and the
+/// mean square per 100 ms step is combined into the momentary (400 ms) and short-term (3 s)
+/// loudness. The momentary windows double as the gating blocks for the integrated loudness, and
+/// the short-term values feed the loudness range as described in EBU Tech 3342.
+#[derive(Default)]
+pub struct LoudnessMeter {
+    step_len: usize,
+
+    channels: Vec<KWeighting>,
+    /// The sum of the K-weighted squares of all channels during the current step.
+    step_sum: f64,
+    step_position: usize,
+    /// The mean squares of the last [`SHORT_TERM_STEPS`] steps, as a ring buffer.
+    step_powers: [f32; SHORT_TERM_STEPS],
+    step_idx: usize,
+    /// The number of steps measured so far, up to [`SHORT_TERM_STEPS`].
+    num_steps: usize,
+
+    momentary: f32,
+    short_term: f32,
+    integrated: f32,
+    range: f32,
+    /// The number of gating blocks per 0.1 LU bin, for the integrated loudness.
+    block_histogram: Vec<u32>,
+    /// The number of short-term values per 0.1 LU bin, for the loudness range.
+    short_term_histogram: Vec<u32>,
+}
+
+impl LoudnessMeter {
+    /// Allocate state for `num_channels` channels. This must be called from `initialize()`.
+    pub fn initialize(&mut self, num_channels: usize, sample_rate: f32) {
+        self.step_len = ((sample_rate * STEP_MS / 1000.0).round() as usize).max(1);
+
+        self.channels.resize_with(num_channels, KWeighting::default);
+        for channel in &mut self.channels {
+            channel.set_sample_rate(sample_rate);
+        }
+        self.block_histogram = vec![0; HISTOGRAM_LEN];
+        self.short_term_histogram = vec![0; HISTOGRAM_LEN];
+
+        self.reset();
+    }
+
+    /// Clear the filters and start a new measurement.
+    pub fn reset(&mut self) {
+        for channel in &mut self.channels {
+            channel.reset();
+        }
+
+        self.step_sum = 0.0;
+        self.step_position = 0;
+        self.step_powers = [0.0; SHORT_TERM_STEPS];
+        self.step_idx = 0;
+        self.num_steps = 0;
+
+        self.momentary = f32::NEG_INFINITY;
+        self.short_term = f32::NEG_INFINITY;
+        self.integrated = f32::NEG_INFINITY;
+        self.range = f32::NEG_INFINITY;
+        self.block_histogram.fill(0);
+        self.short_term_histogram.fill(0);
+    }
+
+    /// Measure a single frame containing one sample for every channel. All channels are weighted
+    /// equally, which is correct for the mono and stereo layouts.
+    pub fn process(&mut self, frame: &[f32]) {
+        for (channel, sample) in self.channels.iter_mut().zip(frame) {
+            let weighted = channel.process(*sample);
+            self.step_sum += (weighted * weighted) as f64;
+        }
+
+        self.step_position += 1;
+        if self.step_position == self.step_len {
+            self.finish_step();
+        }
+    }
+
+    /// Store the current readings in `readings` for the editor.
+    pub fn publish(&self, readings: &LoudnessReadings) {
+        readings.momentary.set(self.momentary);
+        readings.short_term.set(self.short_term);
+        readings.integrated.set(self.integrated);
+        readings.range.set(self.range);
+    }
+
+    fn finish_step(&mut self) {
+        self.step_powers[self.step_idx] = (self.step_sum / self.step_len as f64) as f32;
+        self.step_idx = (self.step_idx + 1) % SHORT_TERM_STEPS;
+        self.num_steps = (self.num_steps + 1).min(SHORT_TERM_STEPS);
+        self.step_sum = 0.0;
+        self.step_position = 0;
+
+        if self.num_steps >= MOMENTARY_STEPS {
+            self.momentary = loudness(self.window_power(MOMENTARY_STEPS));
+            if let Some(bin_idx) = histogram_bin(self.momentary) {
+                self.block_histogram[bin_idx] += 1;
+                self.integrated = integrated_loudness(&self.block_histogram);
+            }
+        }
+
+        if self.num_steps >= SHORT_TERM_STEPS {
+            self.short_term = loudness(self.window_power(SHORT_TERM_STEPS));
+            if let Some(bin_idx) = histogram_bin(self.short_term) {
+                self.short_term_histogram[bin_idx] += 1;
+                self.range = loudness_range(&self.short_term_histogram);
+            }
+        }
+    }
+
+    /// The mean square of the last `num_steps` steps.
+    fn window_power(&self, num_steps: usize) -> f32 {
+        let sum: f32 = (1..=num_steps)
+            .map(|offset| {
+                self.step_powers[(self.step_idx + SHORT_TERM_STEPS - offset) % SHORT_TERM_STEPS]
+            })
+            .sum();
+
+        sum / num_steps as f32
+    }
+}
+
+/// Convert a K-weighted mean square to LUFS.
+fn loudness(power: f32) -> f32 {
+    if power > 0.0 {
+        -0.691 + 10.0 * power.log10()
+    } else {
+        f32::NEG_INFINITY
+    }
+}
+
+/// The inverse of [`loudness()`].
+fn power(loudness: f32) -> f32 {
+    10.0f32.powf((loudness + 0.691) / 10.0)
+}
+
+/// The histogram bin for a loudness value, or `None` if the value is below the absolute gate.
+fn histogram_bin(loudness: f32) -> Option<usize> {
+    if loudness < ABSOLUTE_GATE_LUFS {
+        return None;
+    }
+
+    let bin_idx = ((loudness - ABSOLUTE_GATE_LUFS) * HISTOGRAM_BINS_PER_LU) as usize;
+    Some(bin_idx.min(HISTOGRAM_LEN - 1))
+}
+
+/// The loudness at the center of a histogram bin.
+fn bin_loudness(bin_idx: usize) -> f32 {
+    ABSOLUTE_GATE_LUFS + (bin_idx as f32 + 0.5) / HISTOGRAM_BINS_PER_LU
+}
+
+/// The average loudness of the bins starting at `first_bin`. Returns `None` for empty bins.
+fn average_loudness(histogram: &[u32], first_bin: usize) -> Option<f32> {
+    let (power_sum, count) = histogram.iter().enumerate().skip(first_bin).fold(
+        (0.0f64, 0u64),
+        |(power_sum, count), (bin_idx, bin_count)| {
+            (
+                power_sum + power(bin_loudness(bin_idx)) as f64 * *bin_count as f64,
+                count + *bin_count as u64,
+            )
+        },
+    );
+
+    (count > 0).then(|| loudness((power_sum / count as f64) as f32))
+}
+
+/// The integrated loudness from the absolute gated blocks, with the relative gate applied.
+fn integrated_loudness(block_histogram: &[u32]) -> f32 {
+    let Some(ungated) = average_loudness(block_histogram, 0) else {
+        return f32::NEG_INFINITY;
+    };
+    let relative_gate_bin = histogram_bin(ungated + INTEGRATED_RELATIVE_GATE_LU).unwrap_or(0);
+
+    average_loudness(block_histogram, relative_gate_bin).unwrap_or(f32::NEG_INFINITY)
+}
+
+/// The loudness range is the spread between the 10th and the 95th percentile of the relative
+/// gated short-term loudness values.
+fn loudness_range(short_term_histogram: &[u32]) -> f32 {
+    let Some(ungated) = average_loudness(short_term_histogram, 0) else {
+        return f32::NEG_INFINITY;
+    };
+    let relative_gate_bin = histogram_bin(ungated + RANGE_RELATIVE_GATE_LU).unwrap_or(0);
+    let gated = &short_term_histogram[relative_gate_bin..];
+
+    let count: u64 = gated.iter().map(|bin_count| *bin_count as u64).sum();
+    if count == 0 {
+        return f32::NEG_INFINITY;
+    }
+
+    let percentile_bin = |percentile: f32| {
+        let target = ((count - 1) as f32 * percentile).round() as u64;
+        let mut seen = 0;
+        for (bin_idx, bin_count) in gated.iter().enumerate() {
+            seen += *bin_count as u64;
+            if seen > target {
+                return relative_gate_bin + bin_idx;
+            }
+        }
+
+        relative_gate_bin + gated.len() - 1
+    };
+
+    bin_loudness(percentile_bin(RANGE_HIGH_PERCENTILE))
+        - bin_loudness(percentile_bin(RANGE_LOW_PERCENTILE))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f32::consts::PI;
+
+    const SAMPLE_RATE: f32 = 48_000.0;
+
+    /// Measure a stereo 1 kHz sine made up of `(level_dbfs, seconds)` segments, like the test
+    /// signals from EBU Tech 3341 and 3342. The levels are the sine's peak level per channel.
+    fn measure(segments: &[(f32, f32)]) -> LoudnessMeter {
+        let mut meter = LoudnessMeter::default();
+        meter.initialize(2, SAMPLE_RATE);
+
+        let mut sample_idx = 0;
+        for (level_dbfs, seconds) in segments {
+            let amplitude = 10.0f32.powf(level_dbfs / 20.0);
+            for _ in 0..(seconds * SAMPLE_RATE) as usize {
+                // A period is exactly 48 samples, which keeps the phase accurate for long signals
+                let phase = 2.0 * PI * 1000.0 * (sample_idx % 48) as f32 / SAMPLE_RATE;
+                let sample = phase.sin() * amplitude;
+                meter.process(&[sample, sample]);
+                sample_idx += 1;
+            }
+        }
+
+        meter
+    }
+
+    fn assert_near(measured: f32, expected: f32, tolerance: f32) {
+        assert!(
+            (measured - expected).abs() <= tolerance,
+            "measured {measured}, expected {expected} ± {tolerance}"
+        );
+    }
+
+    #[test]
+    fn tech_3341_steady_sines() {
+        for level in [-23.0, -33.0] {
+            let meter = measure(&[(level, 20.0)]);
+            assert_near(meter.momentary, level, 0.1);
+            assert_near(meter.short_term, level, 0.1);
+            assert_near(meter.integrated, level, 0.1);
+        }
+    }
+
+    #[test]
+    fn tech_3341_relative_gate() {
+        let meter = measure(&[(-36.0, 10.0), (-23.0, 60.0), (-36.0, 10.0)]);
+        assert_near(meter.integrated, -23.0, 0.1);
+    }
+
+    #[test]
+    fn tech_3341_absolute_gate() {
+        let meter = measure(&[
+            (-72.0, 10.0),
+            (-36.0, 10.0),
+            (-23.0, 60.0),
+            (-36.0, 10.0),
+            (-72.0, 10.0),
+        ]);
+        assert_near(meter.integrated, -23.0, 0.1);
+    }
+
+    #[test]
+    fn tech_3342_loudness_range() {
+        for (quiet, loud, range) in [
+            (-20.0, -15.0, 5.0),
+            (-30.0, -20.0, 10.0),
+            (-40.0, -20.0, 20.0),
+        ] {
+            let meter = measure(&[(quiet, 20.0), (loud, 20.0)]);
+            assert_near(meter.range, range, 1.0);
+        }
+    }
+
+    #[test]
+    fn reset_starts_over() {
+        let mut meter = measure(&[(-23.0, 5.0)]);
+        meter.reset();
+        assert_eq!(meter.integrated, f32::NEG_INFINITY);
+        assert_eq!(meter.range, f32::NEG_INFINITY);
+    }
+}
@@ -0,0 +1,158 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use super::bus::{AudioQueue, Reading};
+use crate::MAX_CHANNELS;
+
+/// The time spans the oscilloscope can show, from zoomed in to zoomed out.
+pub const TIMEBASES_MS: [f32; 7] = [2.5, 5.0, 10.0, 20.0, 50.0, 100.0, 200.0];
+/// The number of columns the trace is reduced to. Every column stores the minimum and maximum of
+/// the samples it covers, so short peaks don't disappear when zoomed out.
+pub const NUM_COLUMNS: usize = 400;
+
+/// The queue holds enough frames for the editor to skip a frame at 192 kHz.
+const QUEUE_CAPACITY: usize = 16_384;
+/// The trigger only rearms once the signal has dropped below minus this level, so noise around
+/// the zero crossing doesn't make it fire on the wrong edge.
+const TRIGGER_HYSTERESIS: f32 = 0.01;
+
+/// Carries the plugin's output from the audio thread to the editor's oscilloscope.
+pub struct OscilloscopeBuffer {
+    sample_rate: Reading,
+    num_channels: AtomicUsize,
+    frames: AudioQueue<[f32; MAX_CHANNELS]>,
+}
+
+impl Default for OscilloscopeBuffer {
+    fn default() -> Self {
+        Self {
+            sample_rate: Reading::new(44_100.0),
+            num_channels: AtomicUsize::new(MAX_CHANNELS),
+            frames: AudioQueue::new(QUEUE_CAPACITY),
+        }
+    }
+}
+
+impl OscilloscopeBuffer {
+    /// Called from `initialize()` so the editor knows how many samples to show.
+    pub fn initialize(&self, num_channels: usize, sample_rate: f32) {
+        self.num_channels.store(num_channels, Ordering::Relaxed);
+        self.sample_rate.set(sample_rate);
+    }
+
+    /// Add a frame containing one sample for every channel.
+    pub fn push(&self, frame: &[f32]) {
+        let mut padded = [0.0; MAX_CHANNELS];
+        padded[..frame.len()].copy_from_slice(frame);
+        self.frames.push(padded);
+    }
+}
+
+/// Keeps the recent output for the editor and finds the part of it to show. The trace starts at
+/// a rising zero crossing of the first channel, so a steady note holds still. When there's no
+/// crossing, like during silence or with a very low note, the latest audio is shown instead.
+#[derive(Default)]
+pub struct Oscilloscope {
+    /// The recent frames, as a ring buffer. This holds two of the longest time spans, so there's
+    /// always room to search for a trigger before the part that's shown.
+    history: Vec<[f32; MAX_CHANNELS]>,
+    position: usize,
+    sample_rate: f32,
+    num_channels: usize,
+
+    /// `columns[channel][column]` is the `(min, max)` range of the samples in that column.
+    columns: [Vec<(f32, f32)>; MAX_CHANNELS],
+    triggered: bool,
+}
+
+impl Oscilloscope {
+    /// Take the frames received from `buffer` since the last call and update the trace for a time
+    /// span of `timebase_ms`. This should be called once per frame.
+    pub fn update(&mut self, buffer: &OscilloscopeBuffer, timebase_ms: f32) {
+        self.num_channels = buffer
+            .num_channels
+            .load(Ordering::Relaxed)
+            .min(MAX_CHANNELS);
+        let sample_rate = buffer.sample_rate.get();
+        if sample_rate != self.sample_rate {
+            self.sample_rate = sample_rate;
+            let max_timebase_ms = TIMEBASES_MS[TIMEBASES_MS.len() - 1];
+            let history_len = (sample_rate * max_timebase_ms / 1000.0).ceil() as usize * 2;
+            self.history = vec![[0.0; MAX_CHANNELS]; history_len.max(2)];
+            self.position = 0;
+        }
+
+        let history_len = self.history.len();
+        while let Some(frame) = buffer.frames.pop() {
+            self.history[self.position] = frame;
+            self.position = (self.position + 1) % history_len;
+        }
+
+        let window =
+            ((sample_rate * timebase_ms / 1000.0).round() as usize).clamp(1, history_len / 2);
+        let trigger = self.find_trigger(window);
+        self.triggered = trigger.is_some();
+        // Without a trigger the window ends at the newest frame
+        let start = trigger.unwrap_or(history_len - window);
+
+        for (channel_idx, columns) in self.columns[..self.num_channels].iter_mut().enumerate() {
+            columns.clear();
+            for column_idx in 0..NUM_COLUMNS {
+                let first = start + column_idx * window / NUM_COLUMNS;
+                let last = (start + (column_idx + 1) * window / NUM_COLUMNS).max(first + 1);
+
+                let range = (first..last)
+                    .map(|age_idx| {
+                        self.history[(self.position + age_idx) % history_len][channel_idx]
+                    })
+                    .fold((f32::INFINITY, f32::NEG_INFINITY), |(min, max), sample| {
+                        (min.min(sample), max.max(sample))
+                    });
+                columns.push(range);
+            }
+        }
+    }
+
+    /// The latest rising zero crossing that still leaves room for `window` frames after it,
+    /// searched back as far as the longest time span so even zoomed in views of low notes can
+    /// trigger. The result counts from the oldest frame in the history.
+    fn find_trigger(&self, window: usize) -> Option<usize> {
+        // The channels aren't summed, since out of phase channels would cancel each other out
+        let history_len = self.history.len();
+        let mut armed = false;
+        let mut trigger = None;
+        for age_idx in history_len / 2 - window..=history_len - window {
+            let sample = self.history[(self.position + age_idx) % history_len][0];
+            if sample < -TRIGGER_HYSTERESIS {
+                armed = true;
+            } else if armed && sample >= 0.0 {
+                armed = false;
+                trigger = Some(age_idx);
+            }
+        }
+
+        trigger
+    }
+
+    pub fn num_channels(&self) -> usize {
+        self.num_channels
+    }
+
+    /// The `(min, max)` range for each of the [`NUM_COLUMNS`] columns of a channel's trace.
+    pub fn columns(&self, channel_idx: usize) -> &[(f32, f32)] {
+        &self.columns[channel_idx]
+    }
+
+    /// The newest `count` frames, from oldest to newest. The goniometer draws these.
+    pub fn recent_frames(&self, count: usize) -> impl Iterator<Item = &[f32; MAX_CHANNELS]> {
+        let history_len = self.history.len();
+        let count = count.min(history_len);
+
+        (history_len - count..history_len)
+            .map(move |age_idx| &self.history[(self.position + age_idx) % history_len])
+    }
+
+    /// Whether the trace is lined up with a trigger, or free running.
+    pub fn triggered(&self) -> bool {
+        self.triggered
+    }
+}
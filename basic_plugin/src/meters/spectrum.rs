@@ -0,0 +1,278 @@
+use std::f32::consts::PI;
+use std::time::Instant;
+
+use super::bus::{self, FrameBuffer, FrameSender, Reading};
+use super::downmix;
+use crate::dsp::fft::Fft;
+
+/// The analysis window. At 48 kHz this resolves about 12 Hz, which is enough to tell the bass
+/// notes apart.
+const FFT_SIZE: usize = 4096;
+/// A new window is published every time this many samples have come in, so consecutive windows
+/// overlap by three quarters.
+const HOP_SIZE: usize = FFT_SIZE / 4;
+
+/// The analyzer shows this frequency range on a logarithmic axis.
+pub const MIN_FREQUENCY: f32 = 20.0;
+pub const MAX_FREQUENCY: f32 = 20_000.0;
+/// The number of points on the analyzer's frequency axis.
+pub const NUM_POINTS: usize = 256;
+/// Magnitudes are clamped to this level so silence doesn't turn into negative infinity.
+pub const FLOOR_DB: f32 = -100.0;
+
+/// The smoothing time constants for rising and falling magnitudes. Peaks show up quickly and
+/// then fall back slowly, which keeps the curves readable.
+const RISE_MS: f32 = 20.0;
+const FALL_MS: f32 = 300.0;
+
+/// Carries windows of the plugin's input and output from the audio thread to the editor's
+/// spectrum analyzer.
+pub struct SpectrumBuffers {
+    sample_rate: Reading,
+    input: FrameBuffer<Vec<f32>>,
+    output: FrameBuffer<Vec<f32>>,
+}
+
+/// The audio thread's end of [`SpectrumBuffers`].
+pub struct SpectrumSender {
+    input: WindowSender,
+    output: WindowSender,
+}
+
+/// Collects the input or the output on the audio thread, and publishes the last [`FFT_SIZE`]
+/// samples in order every [`HOP_SIZE`] samples.
+struct WindowSender {
+    /// The last [`FFT_SIZE`] samples, as a ring buffer.
+    history: Vec<f32>,
+    position: usize,
+    /// The number of samples since the last window was published.
+    hop_counter: usize,
+    windows: FrameSender<Vec<f32>>,
+}
+
+impl SpectrumBuffers {
+    pub fn new() -> (SpectrumSender, Self) {
+        let (input_sender, input) = WindowSender::new();
+        let (output_sender, output) = WindowSender::new();
+
+        (
+            SpectrumSender {
+                input: input_sender,
+                output: output_sender,
+            },
+            Self {
+                sample_rate: Reading::new(44_100.0),
+                input,
+                output,
+            },
+        )
+    }
+
+    /// Called from `initialize()` so the editor can map the FFT bins to frequencies.
+    pub fn set_sample_rate(&self, sample_rate: f32) {
+        self.sample_rate.set(sample_rate);
+    }
+}
+
+impl SpectrumSender {
+    /// Add a frame of the plugin's input. The channels are summed to mono.
+    pub fn push_input(&mut self, frame: &[f32]) {
+        self.input.push(downmix(frame));
+    }
+
+    /// Add a frame of the plugin's output. The channels are summed to mono.
+    pub fn push_output(&mut self, frame: &[f32]) {
+        self.output.push(downmix(frame));
+    }
+}
+
+impl WindowSender {
+    fn new() -> (Self, FrameBuffer<Vec<f32>>) {
+        let (windows, buffer) = bus::frame_buffer(&vec![0.0; FFT_SIZE]);
+
+        (
+            Self {
+                history: vec![0.0; FFT_SIZE],
+                position: 0,
+                hop_counter: 0,
+                windows,
+            },
+            buffer,
+        )
+    }
+
+    fn push(&mut self, sample: f32) {
+        self.history[self.position] = sample;
+        self.position = (self.position + 1) % FFT_SIZE;
+
+        self.hop_counter += 1;
+        if self.hop_counter == HOP_SIZE {
+            self.hop_counter = 0;
+
+            // The ring buffer's oldest sample is at the write position
+            let (newest, oldest) = self.history.split_at(self.position);
+            self.windows.publish(|window| {
+                window[..oldest.len()].copy_from_slice(oldest);
+                window[oldest.len()..].copy_from_slice(newest);
+            });
+        }
+    }
+}
+
+/// Turns the windows from [`SpectrumBuffers`] into smoothed magnitude curves on a logarithmic
+/// frequency axis. This runs on the editor's thread.
+pub struct SpectrumAnalyzer {
+    fft: Fft,
+    /// A Hann window, scaled so a full scale sine wave reads as 0 dB.
+    window: Vec<f32>,
+    real: Vec<f32>,
+    imaginary: Vec<f32>,
+
+    input: Curve,
+    output: Curve,
+    last_update: Option<Instant>,
+}
+
+/// The analysis state for the input or the output.
+struct Curve {
+    /// The newest window received from the audio thread, oldest sample first.
+    samples: Vec<f32>,
+    /// The smoothed magnitude in decibels for every point on the frequency axis.
+    magnitudes_db: Vec<f32>,
+    /// The unsmoothed magnitudes from the last transform.
+    target_db: Vec<f32>,
+}
+
+impl Curve {
+    fn new() -> Self {
+        Self {
+            samples: vec![0.0; FFT_SIZE],
+            magnitudes_db: vec![FLOOR_DB; NUM_POINTS],
+            target_db: vec![FLOOR_DB; NUM_POINTS],
+        }
+    }
+
+    /// Take the newest window if the audio thread published one. Returns `true` if it did.
+    fn receive(&mut self, windows: &FrameBuffer<Vec<f32>>) -> bool {
+        windows.read_new(|window| self.samples.copy_from_slice(window))
+    }
+
+    fn smooth(&mut self, rise_weight: f32, fall_weight: f32) {
+        for (magnitude, target) in self.magnitudes_db.iter_mut().zip(&self.target_db) {
+            let weight = if *target > *magnitude {
+                rise_weight
+            } else {
+                fall_weight
+            };
+            *magnitude = *magnitude * weight + target * (1.0 - weight);
+        }
+    }
+}
+
+impl Default for SpectrumAnalyzer {
+    fn default() -> Self {
+        let window: Vec<f32> = (0..FFT_SIZE)
+            .map(|idx| 0.5 - 0.5 * (2.0 * PI * idx as f32 / FFT_SIZE as f32).cos())
+            .collect();
+        let window_sum: f32 = window.iter().sum();
+        let window = window.iter().map(|x| x * 2.0 / window_sum).collect();
+
+        Self {
+            fft: Fft::new(FFT_SIZE),
+            window,
+            real: vec![0.0; FFT_SIZE],
+            imaginary: vec![0.0; FFT_SIZE],
+
+            input: Curve::new(),
+            output: Curve::new(),
+            last_update: None,
+        }
+    }
+}
+
+impl SpectrumAnalyzer {
+    /// Analyze the newest windows received from `buffers` since the last call and advance the
+    /// smoothing. This should be called once per frame.
+    pub fn update(&mut self, buffers: &SpectrumBuffers) {
+        let now = Instant::now();
+        let elapsed_ms = self.last_update.map_or(0.0, |last_update| {
+            (now - last_update).as_secs_f32() * 1000.0
+        });
+        self.last_update = Some(now);
+
+        let sample_rate = buffers.sample_rate.get();
+        if self.input.receive(&buffers.input) {
+            self.analyze(sample_rate, false);
+        }
+        if self.output.receive(&buffers.output) {
+            self.analyze(sample_rate, true);
+        }
+
+        let rise_weight = (-elapsed_ms / RISE_MS).exp();
+        let fall_weight = (-elapsed_ms / FALL_MS).exp();
+        self.input.smooth(rise_weight, fall_weight);
+        self.output.smooth(rise_weight, fall_weight);
+    }
+
+    /// The input's smoothed magnitudes in decibels, one for each of the [`NUM_POINTS`] points on
+    /// the frequency axis.
+    pub fn input_db(&self) -> &[f32] {
+        &self.input.magnitudes_db
+    }
+
+    /// The output's smoothed magnitudes in decibels.
+    pub fn output_db(&self) -> &[f32] {
+        &self.output.magnitudes_db
+    }
+
+    fn analyze(&mut self, sample_rate: f32, output: bool) {
+        let curve = if output {
+            &mut self.output
+        } else {
+            &mut self.input
+        };
+
+        for ((real, sample), window) in self.real.iter_mut().zip(&curve.samples).zip(&self.window) {
+            *real = sample * window;
+        }
+        self.imaginary.fill(0.0);
+        self.fft.process(&mut self.real, &mut self.imaginary);
+
+        // Only the first half of the spectrum is needed for a real signal
+        for (real, imaginary) in self.real[..FFT_SIZE / 2]
+            .iter_mut()
+            .zip(&self.imaginary[..FFT_SIZE / 2])
+        {
+            *real = (*real * *real + imaginary * imaginary).sqrt();
+        }
+        let magnitudes = &self.real[..FFT_SIZE / 2];
+
+        let bins_per_hz = FFT_SIZE as f32 / sample_rate;
+        for (point_idx, target) in curve.target_db.iter_mut().enumerate() {
+            let low_bin = point_frequency(point_idx as f32) * bins_per_hz;
+            let high_bin = point_frequency(point_idx as f32 + 1.0) * bins_per_hz;
+
+            // At the low end a point is narrower than a bin, so the bins are interpolated. Further
+            // up a point spans several bins, and the loudest one is shown.
+            let first_bin = low_bin.ceil() as usize;
+            let last_bin = (high_bin.floor() as usize).min(magnitudes.len() - 1);
+            let magnitude = if first_bin <= last_bin {
+                magnitudes[first_bin..=last_bin]
+                    .iter()
+                    .fold(0.0f32, |peak, magnitude| peak.max(*magnitude))
+            } else {
+                let center_bin = (low_bin + high_bin) / 2.0;
+                let bin_idx = (center_bin.floor() as usize).min(magnitudes.len() - 2);
+                let t = (center_bin - bin_idx as f32).min(1.0);
+                magnitudes[bin_idx] * (1.0 - t) + magnitudes[bin_idx + 1] * t
+            };
+
+            *target = (20.0 * magnitude.log10()).max(FLOOR_DB);
+        }
+    }
+}
+
+/// The frequency at a (fractional) point on the analyzer's frequency axis.
+fn point_frequency(point: f32) -> f32 {
+    MIN_FREQUENCY * (MAX_FREQUENCY / MIN_FREQUENCY).powf(point / NUM_POINTS as f32)
+}
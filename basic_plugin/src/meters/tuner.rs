@@ -0,0 +1,343 @@
+use std::time::{Duration, Instant};
+
+use super::bus::{AudioQueue, Reading};
+use super::downmix;
+use crate::dsp::biquad::{Biquad, BiquadCoefficients, BUTTERWORTH_Q};
+
+/// The lowest fundamental the tuner detects, a little below the low B string on a five string
+/// bass at 30.9 Hz.
+const MIN_FREQUENCY: f32 = 28.0;
+/// The highest fundamental the tuner detects, which leaves room above the top fret of a bass.
+const MAX_FREQUENCY: f32 = 1000.0;
+/// The input is decimated to about this rate before the analysis. The fundamentals and the first
+/// few harmonics of a bass fit in easily, and the analysis gets a lot cheaper.
+const ANALYSIS_RATE: f32 = 8000.0;
+/// The cutoff of the low-pass filter in front of the decimation, safely below the lowest
+/// decimated Nyquist frequency.
+const ANTI_ALIAS_FREQUENCY: f32 = 2000.0;
+/// The number of decimated samples that are compared for every lag. This spans a few periods of
+/// the lowest note.
+const WINDOW_LEN: usize = 1024;
+/// The threshold for YIN's normalized difference. The first dip below this is taken as the
+/// period, which keeps the detector from jumping down an octave.
+const YIN_THRESHOLD: f32 = 0.15;
+/// Input below this RMS level, about -50 dBFS, counts as silence.
+const SILENCE_RMS: f32 = 0.003;
+/// Detections within this many cents of the shown pitch are smoothed, anything further away is
+/// taken as a new note.
+const SMOOTHING_RANGE_CENTS: f32 = 50.0;
+const SMOOTHING_FACTOR: f32 = 0.3;
+/// How long the last note stays on screen after the signal stops, so it doesn't flicker off
+/// between notes.
+const HOLD_TIME: Duration = Duration::from_millis(500);
+/// The queue holds enough samples for the editor to skip a frame at 192 kHz.
+const QUEUE_CAPACITY: usize = 16_384;
+
+const NOTE_NAMES: [&str; 12] = [
+    "C", "C#", "D", "D#", "E", "F", "F#", "G", "G#", "A", "A#", "B",
+];
+
+/// Carries the plugin's input from the audio thread to the editor's tuner.
+pub struct TunerBuffer {
+    sample_rate: Reading,
+    samples: AudioQueue<f32>,
+}
+
+impl Default for TunerBuffer {
+    fn default() -> Self {
+        Self {
+            sample_rate: Reading::new(44_100.0),
+            samples: AudioQueue::new(QUEUE_CAPACITY),
+        }
+    }
+}
+
+impl TunerBuffer {
+    /// Called from `initialize()` so the editor can convert periods to frequencies.
+    pub fn set_sample_rate(&self, sample_rate: f32) {
+        self.sample_rate.set(sample_rate);
+    }
+
+    /// Add a frame of the plugin's input. The channels are summed to mono.
+    pub fn push(&self, frame: &[f32]) {
+        self.samples.push(downmix(frame));
+    }
+}
+
+/// A detected pitch, relative to the nearest note in twelve-tone equal temperament with A4 at
+/// 440 Hz.
+#[derive(Debug, Clone, Copy)]
+pub struct Pitch {
+    pub frequency: f32,
+    /// The semitone within the octave, starting at C.
+    note: usize,
+    /// The octave in scientific pitch notation, so the low E string on a bass is E1.
+    pub octave: i32,
+    /// How far the pitch is from the nearest note, between -50 and +50 cents.
+    pub cents: f32,
+}
+
+impl Pitch {
+    fn from_frequency(frequency: f32) -> Self {
+        let midi_note = 69.0 + 12.0 * (frequency / 440.0).log2();
+        let nearest_note = midi_note.round();
+
+        Self {
+            frequency,
+            note: (nearest_note as i32).rem_euclid(12) as usize,
+            octave: (nearest_note as i32).div_euclid(12) - 1,
+            cents: (midi_note - nearest_note) * 100.0,
+        }
+    }
+
+    pub fn note_name(&self) -> &'static str {
+        NOTE_NAMES[self.note]
+    }
+}
+
+/// A monophonic pitch detector for bass, using the YIN algorithm. The input is low-passed and
+/// decimated first, and the fundamental is found as the shortest lag where the signal closely
+/// matches a delayed copy of itself. This runs on the editor's thread.
+#[derive(Default)]
+pub struct Tuner {
+    sample_rate: f32,
+    /// Only every `decimation`th filtered input sample is kept.
+    decimation: usize,
+    decimation_counter: usize,
+    /// Two cascaded Butterworth sections form a fourth order low-pass filter.
+    anti_alias: [Biquad; 2],
+
+    /// The recent decimated input, as a ring buffer. This is long enough for the analysis window
+    /// plus the longest lag.
+    history: Vec<f32>,
+    position: usize,
+    /// Scratch space for the analysis, allocated together with the history.
+    window: Vec<f32>,
+    difference: Vec<f32>,
+
+    pitch: Option<Pitch>,
+    last_detection: Option<Instant>,
+}
+
+impl Tuner {
+    /// Analyze the samples received from `buffer` since the last call. This should be called once
+    /// per frame.
+    pub fn update(&mut self, buffer: &TunerBuffer) {
+        let sample_rate = buffer.sample_rate.get();
+        if sample_rate != self.sample_rate {
+            self.configure(sample_rate);
+        }
+
+        let mut received = false;
+        while let Some(sample) = buffer.samples.pop() {
+            let filtered = self
+                .anti_alias
+                .iter_mut()
+                .fold(sample, |sample, filter| filter.process(sample));
+            self.decimation_counter += 1;
+            if self.decimation_counter == self.decimation {
+                self.decimation_counter = 0;
+                self.history[self.position] = filtered;
+                self.position = (self.position + 1) % self.history.len();
+                received = true;
+            }
+        }
+
+        let now = Instant::now();
+        if let Some(frequency) = received.then(|| self.detect()).flatten() {
+            let frequency = match self.pitch {
+                Some(pitch)
+                    if (1200.0 * (frequency / pitch.frequency).log2()).abs()
+                        < SMOOTHING_RANGE_CENTS =>
+                {
+                    pitch.frequency * (frequency / pitch.frequency).powf(SMOOTHING_FACTOR)
+                }
+                _ => frequency,
+            };
+
+            self.pitch = Some(Pitch::from_frequency(frequency));
+            self.last_detection = Some(now);
+        } else if self
+            .last_detection
+            .is_none_or(|last_detection| now - last_detection >= HOLD_TIME)
+        {
+            self.pitch = None;
+        }
+    }
+
+    /// The current pitch, or `None` if there's no clear pitch in the input.
+    pub fn pitch(&self) -> Option<Pitch> {
+        self.pitch
+    }
+
+    fn configure(&mut self, sample_rate: f32) {
+        self.sample_rate = sample_rate;
+        self.decimation = ((sample_rate / ANALYSIS_RATE).round() as usize).max(1);
+        self.decimation_counter = 0;
+
+        let coefficients =
+            BiquadCoefficients::lowpass(sample_rate, ANTI_ALIAS_FREQUENCY, BUTTERWORTH_Q);
+        for filter in &mut self.anti_alias {
+            filter.coefficients = coefficients;
+            filter.reset();
+        }
+
+        let max_lag = self.max_lag();
+        self.history = vec![0.0; WINDOW_LEN + max_lag + 1];
+        self.position = 0;
+        self.window = vec![0.0; WINDOW_LEN + max_lag + 1];
+        self.difference = vec![0.0; max_lag + 1];
+
+        self.pitch = None;
+        self.last_detection = None;
+    }
+
+    fn analysis_rate(&self) -> f32 {
+        self.sample_rate / self.decimation as f32
+    }
+
+    fn max_lag(&self) -> usize {
+        (self.analysis_rate() / MIN_FREQUENCY).ceil() as usize
+    }
+
+    /// Run YIN on the history. Returns the fundamental frequency, or `None` during silence or
+    /// when there's no clear period.
+    fn detect(&mut self) -> Option<f32> {
+        // The history is unrolled so the oldest sample comes first
+        let (newer, older) = self.history.split_at(self.position);
+        self.window[..older.len()].copy_from_slice(older);
+        self.window[older.len()..].copy_from_slice(newer);
+
+        let mean_square = self
+            .window
+            .iter()
+            .map(|sample| sample * sample)
+            .sum::<f32>()
+            / self.window.len() as f32;
+        if mean_square.sqrt() < SILENCE_RMS {
+            return None;
+        }
+
+        // The difference function, normalized by its running average so it starts at 1 and dips
+        // towards 0 at multiples of the period
+        let max_lag = self.difference.len() - 1;
+        self.difference[0] = 1.0;
+        let mut running_sum = 0.0;
+        for lag in 1..=max_lag {
+            let difference: f32 = self.window[..WINDOW_LEN]
+                .iter()
+                .zip(&self.window[lag..lag + WINDOW_LEN])
+                .map(|(a, b)| (a - b) * (a - b))
+                .sum();
+            running_sum += difference;
+            self.difference[lag] = if running_sum > 0.0 {
+                difference * lag as f32 / running_sum
+            } else {
+                1.0
+            };
+        }
+
+        // Take the first dip below the threshold, and follow it down to its minimum
+        let min_lag = ((self.analysis_rate() / MAX_FREQUENCY).floor() as usize).max(2);
+        let mut lag = (min_lag..max_lag).find(|&lag| self.difference[lag] < YIN_THRESHOLD)?;
+        while lag + 1 < max_lag && self.difference[lag + 1] < self.difference[lag] {
+            lag += 1;
+        }
+
+        // Parabolic interpolation gives the period with sub-sample precision
+        let (previous, current, next) = (
+            self.difference[lag - 1],
+            self.difference[lag],
+            self.difference[lag + 1],
+        );
+        let curvature = previous - 2.0 * current + next;
+        let offset = if curvature > 0.0 {
+            ((previous - next) / (2.0 * curvature)).clamp(-0.5, 0.5)
+        } else {
+            0.0
+        };
+
+        Some(self.analysis_rate() / (lag as f32 + offset))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f32::consts::PI;
+
+    /// Feed half a second of `signal` to a tuner in editor sized blocks, and return the detected
+    /// pitch.
+    fn detect(sample_rate: f32, signal: impl Fn(f32) -> f32) -> Option<Pitch> {
+        let buffer = TunerBuffer::default();
+        buffer.set_sample_rate(sample_rate);
+        let mut tuner = Tuner::default();
+
+        let block_len = 512;
+        for block_idx in 0..(sample_rate as usize / 2 / block_len) {
+            for sample_idx in block_idx * block_len..(block_idx + 1) * block_len {
+                let sample = signal(sample_idx as f32 / sample_rate);
+                buffer.push(&[sample, sample]);
+            }
+            tuner.update(&buffer);
+        }
+
+        tuner.pitch()
+    }
+
+    fn assert_pitch(pitch: Option<Pitch>, frequency: f32, note_name: &str, octave: i32) {
+        let pitch = pitch.expect("no pitch detected");
+        let error_cents = 1200.0 * (pitch.frequency / frequency).log2();
+        assert!(
+            error_cents.abs() < 3.0,
+            "{frequency} Hz detected as {} Hz",
+            pitch.frequency
+        );
+        assert_eq!((pitch.note_name(), pitch.octave), (note_name, octave));
+    }
+
+    #[test]
+    fn detects_sines() {
+        for sample_rate in [44_100.0, 48_000.0, 96_000.0] {
+            for (frequency, note_name, octave) in [
+                (30.87, "B", 0),
+                (41.2, "E", 1),
+                (55.0, "A", 1),
+                (73.42, "D", 2),
+                (98.0, "G", 2),
+                (392.0, "G", 4),
+            ] {
+                let pitch = detect(sample_rate, |time| {
+                    0.3 * (2.0 * PI * frequency * time).sin()
+                });
+                assert_pitch(pitch, frequency, note_name, octave);
+            }
+        }
+    }
+
+    #[test]
+    fn ignores_strong_harmonics() {
+        // A plucked bass string often has a louder second harmonic than its fundamental
+        let frequency = 41.2;
+        let pitch = detect(48_000.0, |time| {
+            let phase = 2.0 * PI * frequency * time;
+            0.2 * phase.sin()
+                + 0.3 * (2.0 * phase).sin()
+                + 0.15 * (3.0 * phase).sin()
+                + 0.1 * (4.0 * phase).sin()
+        });
+        assert_pitch(pitch, frequency, "E", 1);
+    }
+
+    #[test]
+    fn detects_nothing_in_silence() {
+        assert!(detect(48_000.0, |_| 0.0).is_none());
+    }
+
+    #[test]
+    fn measures_cents() {
+        let pitch = Pitch::from_frequency(440.0 * 2.0f32.powf(20.0 / 1200.0));
+        assert_eq!((pitch.note_name(), pitch.octave), ("A", 4));
+        assert!((pitch.cents - 20.0).abs() < 0.01, "{}", pitch.cents);
+    }
+}
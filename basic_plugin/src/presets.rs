@@ -0,0 +1,81 @@
+//! Factory and user presets. A preset is a snapshot of parameter values by parameter ID, and any
+//! parameter a preset doesn't mention is set to its default. Presets are applied from the editor
+//! through the [`GuiContext`], so the host sees every change as a regular parameter change and
+//! can record and undo it.
+
+use nih_plug::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::BasicParameters;
+
+pub mod factory;
+pub mod user;
+
+/// A named set of parameter values. The values are plain values in the parameters' own units, so
+/// decibels for the parameters shown in dB, gain factors for the saturation gains, 0 or 1 for
+/// toggles, and the variant index for choices. User presets are stored in this form as JSON.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Preset {
+    pub name: String,
+    values: BTreeMap<String, f32>,
+}
+
+impl Preset {
+    fn new(name: &str, values: &[(&str, f32)]) -> Self {
+        Self {
+            name: name.to_string(),
+            values: values
+                .iter()
+                .map(|(id, value)| (id.to_string(), *value))
+                .collect(),
+        }
+    }
+
+    /// Take a snapshot of the current parameter values.
+    pub fn capture(name: &str, params: &BasicParameters) -> Self {
+        let values = params
+            .param_map()
+            .into_iter()
+            // SAFETY: The parameter pointers come from `params`, which outlives this function
+            .map(|(id, param_ptr, _)| (id, unsafe { param_ptr.unmodulated_plain_value() }))
+            .collect();
+
+        Self {
+            name: name.to_string(),
+            values,
+        }
+    }
+
+    /// Set every parameter to this preset's value, or to its default value if the preset doesn't
+    /// mention it. Parameters that already have the right value are left alone. Values for
+    /// parameters that don't exist, for instance in a user preset from an older version, are
+    /// skipped.
+    pub fn apply(&self, params: &BasicParameters, context: &dyn GuiContext) {
+        let param_map = params.param_map();
+        for id in self.values.keys() {
+            if !param_map.iter().any(|(param_id, _, _)| param_id == id) {
+                nih_log!("Preset '{}' sets the unknown parameter '{}'", self.name, id);
+            }
+        }
+
+        for (id, param_ptr, _) in param_map {
+            let value = self.values.get(&id).copied();
+
+            // SAFETY: The parameter pointers come from `params`, which outlives this function
+            unsafe {
+                let normalized = match value {
+                    Some(value) => param_ptr.preview_normalized(value),
+                    None => param_ptr.default_normalized_value(),
+                };
+                if normalized == param_ptr.unmodulated_normalized_value() {
+                    continue;
+                }
+
+                context.raw_begin_set_parameter(param_ptr);
+                context.raw_set_parameter_normalized(param_ptr, normalized);
+                context.raw_end_set_parameter(param_ptr);
+            }
+        }
+    }
+}
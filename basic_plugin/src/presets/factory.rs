@@ -0,0 +1,124 @@
+use nih_plug::prelude::*;
+
+use super::Preset;
+use crate::dsp::saturation::SaturationCurve;
+use crate::dsp::subharmonic::SubOctave;
+
+/// The presets that come with the plugin. The first one restores the default settings.
+pub fn presets() -> Vec<Preset> {
+    vec![
+        Preset::new("Default", &[]),
+        // Light compression and a touch of harmonics for a DI track that should stay natural
+        Preset::new(
+            "Clean DI",
+            &[
+                ("gate_threshold", -60.0),
+                ("gate_range", 40.0),
+                ("comp_threshold", -24.0),
+                ("comp_ratio", 3.0),
+                ("comp_attack", 15.0),
+                ("comp_release", 150.0),
+                ("comp_makeup", 3.0),
+                ("harmonics", 0.2),
+                ("blend", 0.2),
+                ("eq_low_cut_enabled", 1.0),
+                ("eq_low_cut_freq", 30.0),
+                ("lim_ceiling", -1.0),
+            ],
+        ),
+        // Tube saturation and a low-mid push so the bass cuts through a dense mix
+        Preset::new(
+            "Warm Growl",
+            &[
+                ("comp_threshold", -20.0),
+                ("comp_ratio", 4.0),
+                ("sat_curve", variant(SaturationCurve::Tube)),
+                ("sat_drive", util::db_to_gain(12.0)),
+                ("sat_trim", util::db_to_gain(-6.0)),
+                ("sat_mix", 1.0),
+                ("harmonics", 0.6),
+                ("blend", 0.5),
+                ("eq_low_shelf_freq", 80.0),
+                ("eq_low_shelf_gain", 3.0),
+                ("eq_peak_2_freq", 800.0),
+                ("eq_peak_2_gain", 3.0),
+            ],
+        ),
+        // An octave down underneath the bass, kept in mono so it translates to club systems
+        Preset::new(
+            "Sub Boost",
+            &[
+                ("comp_threshold", -22.0),
+                ("comp_ratio", 4.0),
+                ("sub_level", 0.4),
+                ("sub_octave", variant(SubOctave::One)),
+                ("sub_tone", 100.0),
+                ("sub_band", 70.0),
+                ("mono_bass", 1.0),
+                ("mono_freq", 120.0),
+                ("lim_ceiling", -1.0),
+            ],
+        ),
+        // Brings out the attack of a picked bass and clears out the mud underneath
+        Preset::new(
+            "Punchy Pick",
+            &[
+                ("comp_attack", 20.0),
+                ("comp_ratio", 4.0),
+                ("tr_attack", 6.0),
+                ("tr_sustain", -2.0),
+                ("eq_peak_1_freq", 250.0),
+                ("eq_peak_1_gain", -3.0),
+                ("eq_peak_3_freq", 3_500.0),
+                ("eq_peak_3_gain", 4.0),
+            ],
+        ),
+        // Ducks the bass under a kick drum on the sidechain input
+        Preset::new(
+            "Sidechain Pump",
+            &[
+                ("duck_threshold", -30.0),
+                ("duck_depth", 9.0),
+                ("duck_attack", 2.0),
+                ("duck_hold", 30.0),
+                ("duck_release", 200.0),
+            ],
+        ),
+    ]
+}
+
+/// The plain value for a choice parameter.
+fn variant(value: impl Enum) -> f32 {
+    value.to_index() as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::BasicParameters;
+
+    #[test]
+    fn values_are_known_parameters_within_range() {
+        let params = BasicParameters::default();
+        let param_map = params.param_map();
+        for preset in presets() {
+            for (id, value) in &preset.values {
+                let (_, param_ptr, _) = param_map
+                    .iter()
+                    .find(|(param_id, _, _)| param_id == id)
+                    .unwrap_or_else(|| panic!("'{}' sets the unknown '{id}'", preset.name));
+
+                // Normalizing clamps the value to the parameter's range, so a value outside of
+                // that range doesn't survive the round trip
+                // SAFETY: The parameter pointers come from `params`, which outlives this test
+                let round_trip =
+                    unsafe { param_ptr.preview_plain(param_ptr.preview_normalized(*value)) };
+                assert!(
+                    (round_trip - value).abs() <= 1e-4 * value.abs().max(1.0),
+                    "'{}' sets '{id}' to {value}, which is outside of its range",
+                    preset.name
+                );
+            }
+        }
+    }
+}
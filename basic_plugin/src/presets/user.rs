@@ -0,0 +1,241 @@
+use nih_plug::prelude::*;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use super::Preset;
+
+/// The file extension for user presets.
+const EXTENSION: &str = "json";
+
+/// The directory user presets are stored in. This is inside the platform's per-user data
+/// directory, like `~/.local/share` on Linux, `~/Library/Application Support` on macOS, and
+/// `%APPDATA%` on Windows.
+fn preset_dir() -> io::Result<PathBuf> {
+    dirs::data_dir()
+        .map(|data_dir| data_dir.join("PhatBass").join("Basic").join("Presets"))
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no user data directory"))
+}
+
+/// The file name for a preset, without the extension. Characters that aren't safe in file names
+/// are replaced, so the name stored inside of the file is the one that counts.
+fn file_stem(name: &str) -> String {
+    name.chars()
+        .map(|c| {
+            if c.is_alphanumeric() || matches!(c, ' ' | '-' | '_') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+fn preset_path(dir: &Path, name: &str) -> PathBuf {
+    dir.join(file_stem(name)).with_extension(EXTENSION)
+}
+
+/// Whether two preset names end up in the same file. File names are compared ignoring case,
+/// since they are case insensitive on macOS and Windows.
+pub fn same_file(name: &str, other_name: &str) -> bool {
+    file_stem(name).to_lowercase() == file_stem(other_name).to_lowercase()
+}
+
+/// Load every user preset, sorted by name. Files that can't be read are skipped.
+pub fn load_all() -> Vec<Preset> {
+    match preset_dir() {
+        Ok(dir) => load_all_in(&dir),
+        Err(_) => Vec::new(),
+    }
+}
+
+/// Sort presets by name, ignoring case.
+pub fn sort(presets: &mut [Preset]) {
+    presets.sort_by_cached_key(|preset| preset.name.to_lowercase());
+}
+
+/// Write a preset to disk, replacing any existing preset with the same name.
+pub fn save(preset: &Preset) -> io::Result<()> {
+    save_in(&preset_dir()?, preset)
+}
+
+/// Remove a preset's file.
+pub fn delete(name: &str) -> io::Result<()> {
+    fs::remove_file(preset_path(&preset_dir()?, name))
+}
+
+/// Store a preset under a new name. Returns the renamed preset.
+pub fn rename(preset: &Preset, new_name: &str) -> io::Result<Preset> {
+    rename_in(&preset_dir()?, preset, new_name)
+}
+
+fn load_all_in(dir: &Path) -> Vec<Preset> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+
+    let mut presets: Vec<Preset> = entries
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.extension()
+                .is_some_and(|extension| extension == EXTENSION)
+        })
+        .filter_map(|path| {
+            let preset = fs::read_to_string(&path)
+                .and_then(|json| serde_json::from_str::<Preset>(&json).map_err(io::Error::from));
+            match preset {
+                Ok(preset) => Some(preset),
+                Err(err) => {
+                    nih_log!("Skipping the preset at '{}': {}", path.display(), err);
+                    None
+                }
+            }
+        })
+        .collect();
+    sort(&mut presets);
+
+    presets
+}
+
+fn save_in(dir: &Path, preset: &Preset) -> io::Result<()> {
+    fs::create_dir_all(dir)?;
+    let json = serde_json::to_string_pretty(preset)?;
+
+    fs::write(preset_path(dir, &preset.name), json)
+}
+
+fn rename_in(dir: &Path, preset: &Preset, new_name: &str) -> io::Result<Preset> {
+    let renamed = Preset {
+        name: new_name.to_string(),
+        ..preset.clone()
+    };
+
+    // The file is moved before it's rewritten. On a case insensitive file system, a name that
+    // only differs in case already refers to the old file, and this changes the file name's case.
+    let old_path = preset_path(dir, &preset.name);
+    let new_path = preset_path(dir, new_name);
+    if old_path != new_path {
+        match fs::rename(&old_path, &new_path) {
+            // The file may have been removed outside of the editor, in which case it's recreated
+            Err(err) if err.kind() != io::ErrorKind::NotFound => return Err(err),
+            _ => (),
+        }
+    }
+    save_in(dir, &renamed)?;
+
+    Ok(renamed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An empty directory for a single test, removed again when it's dropped.
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(test_name: &str) -> Self {
+            let path = std::env::temp_dir()
+                .join(format!("basic-presets-{}-{test_name}", std::process::id()));
+            let _ = fs::remove_dir_all(&path);
+
+            Self(path)
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn preset(name: &str) -> Preset {
+        Preset::new(
+            name,
+            &[("gain", -6.0), ("sat_mix", 0.5), ("xover_bands", 2.0)],
+        )
+    }
+
+    #[test]
+    fn round_trip() {
+        let dir = TempDir::new("round_trip");
+        let saved = preset("Round Trip");
+        save_in(&dir.0, &saved).unwrap();
+
+        let loaded = load_all_in(&dir.0);
+        assert_eq!(loaded.len(), 1);
+        assert_eq!(loaded[0].name, "Round Trip");
+        assert_eq!(loaded[0].values, saved.values);
+    }
+
+    #[test]
+    fn names_survive_unsafe_characters() {
+        let dir = TempDir::new("unsafe_characters");
+        save_in(&dir.0, &preset("Sub/Boost: Live?")).unwrap();
+
+        let loaded = load_all_in(&dir.0);
+        assert_eq!(loaded.len(), 1);
+        assert_eq!(loaded[0].name, "Sub/Boost: Live?");
+    }
+
+    #[test]
+    fn rename_moves_the_file() {
+        let dir = TempDir::new("rename");
+        let original = preset("Old Name");
+        save_in(&dir.0, &original).unwrap();
+
+        let renamed = rename_in(&dir.0, &original, "New Name").unwrap();
+        assert_eq!(renamed.name, "New Name");
+
+        let loaded = load_all_in(&dir.0);
+        assert_eq!(loaded.len(), 1);
+        assert_eq!(loaded[0].name, "New Name");
+        assert_eq!(loaded[0].values, original.values);
+        assert!(!preset_path(&dir.0, "Old Name").exists());
+    }
+
+    #[test]
+    fn rename_changes_case() {
+        let dir = TempDir::new("rename_case");
+        let original = preset("bass");
+        save_in(&dir.0, &original).unwrap();
+
+        rename_in(&dir.0, &original, "Bass").unwrap();
+
+        let loaded = load_all_in(&dir.0);
+        assert_eq!(loaded.len(), 1);
+        assert_eq!(loaded[0].name, "Bass");
+    }
+
+    #[test]
+    fn rename_recreates_a_missing_file() {
+        let dir = TempDir::new("rename_missing");
+        fs::create_dir_all(&dir.0).unwrap();
+
+        rename_in(&dir.0, &preset("Gone"), "Back").unwrap();
+
+        let loaded = load_all_in(&dir.0);
+        assert_eq!(loaded.len(), 1);
+        assert_eq!(loaded[0].name, "Back");
+    }
+
+    #[test]
+    fn skips_unreadable_files() {
+        let dir = TempDir::new("unreadable");
+        save_in(&dir.0, &preset("Good")).unwrap();
+        fs::write(dir.0.join("Broken.json"), "{ not json").unwrap();
+        fs::write(dir.0.join("Notes.txt"), "not a preset").unwrap();
+
+        let loaded = load_all_in(&dir.0);
+        assert_eq!(loaded.len(), 1);
+        assert_eq!(loaded[0].name, "Good");
+    }
+
+    #[test]
+    fn detects_file_collisions() {
+        assert!(same_file("Sub/Boost", "sub_boost"));
+        assert!(same_file("Bass", "BASS"));
+        assert!(!same_file("Bass", "Bass 2"));
+    }
+}